@@ -0,0 +1,105 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use gunnyscript::{Lexer, Parser};
+
+// An object with many (mostly long) string properties
+fn strings_doc() -> String {
+    let mut s = String::from("{\n");
+    for i in 0..1000 {
+        s.push_str(&format!(
+            "    /// The string at index {}.\n    str-{} \"Lorem ipsum dolor sit amet, consectetur \\\"adipiscing\\\" elit, sed do eiusmod tempor.\"\n",
+            i, i
+        ));
+        s.push_str(&format!(
+            "    lit-{} ##\"A literal string with \"# inside it.\"##\n",
+            i
+        ));
+    }
+    s.push('}');
+    s
+}
+
+// An array with many numbers, dates and date/times
+fn numbers_doc() -> String {
+    let mut s = String::from("[\n");
+    for i in 0..2000 {
+        s.push_str(&format!(
+            "    {} -{}.{} {}e3 2022-01-02 2022-01-02T03:04:05.678Z // trailing comment\n",
+            i,
+            i,
+            i % 7,
+            i
+        ));
+    }
+    s.push(']');
+    s
+}
+
+// Deeply nested objects and arrays
+fn nested_doc() -> String {
+    let mut s = String::new();
+    for _ in 0..100 {
+        for i in 0..50 {
+            s.push_str(&format!("{{ a{} [ ", i));
+        }
+        s.push_str("null");
+        for _ in 0..50 {
+            s.push_str(" ] }");
+        }
+        s.push('\n');
+    }
+    format!("[\n{}]", s)
+}
+
+// An object with many short properties, one per line
+fn wide_doc() -> String {
+    let mut s = String::from("{\n");
+    for i in 0..20000 {
+        s.push_str(&format!("    p{} {}\n", i, i));
+    }
+    s.push('}');
+    s
+}
+
+// An object with many short properties, all on a single line
+fn minified_doc() -> String {
+    let mut s = String::from("{");
+    for i in 0..20000 {
+        s.push_str(&format!("p{} {},", i, i));
+    }
+    s.push('}');
+    s
+}
+
+fn lexer_benchmarks(c: &mut Criterion) {
+    let docs = [
+        ("strings", strings_doc()),
+        ("numbers", numbers_doc()),
+        ("nested", nested_doc()),
+        ("wide", wide_doc()),
+        ("minified", minified_doc()),
+    ];
+    let mut group = c.benchmark_group("lex");
+    for (name, doc) in &docs {
+        group.throughput(Throughput::Bytes(doc.len() as u64));
+        group.bench_function(*name, |b| {
+            b.iter(|| {
+                for token in Lexer::from(black_box(doc.as_str())) {
+                    black_box(token.unwrap());
+                }
+            })
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("parse");
+    for (name, doc) in &docs {
+        group.throughput(Throughput::Bytes(doc.len() as u64));
+        group.bench_function(*name, |b| {
+            b.iter(|| black_box(Parser::from(black_box(doc.as_str())).parse().unwrap()))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, lexer_benchmarks);
+criterion_main!(benches);
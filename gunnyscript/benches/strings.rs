@@ -0,0 +1,50 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use gunnyscript::Lexer;
+
+// An object with many multi-byte docstrings, property IDs and strings, all of
+// which the lexer slices out of the input
+fn unicode_doc() -> String {
+    let mut s = String::from("{\n");
+    for i in 0..1000 {
+        s.push_str(&format!(
+            "    /// Dökstring {} with some ünicode characters 😂 to validate.\n    prop_{} \"Lörem ipsum dolor sit amet, cönsectetur adipiscing élit 😂\"\n",
+            i, i
+        ));
+        s.push_str(&format!(
+            "    lit_{} #\"Ä literal string with \"quötes\" inside it\"#\n",
+            i
+        ));
+    }
+    s.push('}');
+    s
+}
+
+// Compares lexing as is against lexing while additionally validating each
+// token's text as UTF-8 (as the lexer previously did when extracting slices).
+// The difference between the two is the cost that slicing into the original
+// input avoids.
+fn string_benchmarks(c: &mut Criterion) {
+    let doc = unicode_doc();
+    let mut group = c.benchmark_group("strings");
+    group.throughput(Throughput::Bytes(doc.len() as u64));
+    group.bench_function("sliced", |b| {
+        b.iter(|| {
+            for token in Lexer::from(black_box(doc.as_str())).spanned() {
+                black_box(token.unwrap());
+            }
+        })
+    });
+    group.bench_function("revalidated", |b| {
+        b.iter(|| {
+            for token in Lexer::from(black_box(doc.as_str())).spanned() {
+                let token = token.unwrap();
+                black_box(core::str::from_utf8(&doc.as_bytes()[token.span.clone()]).unwrap());
+                black_box(token);
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, string_benchmarks);
+criterion_main!(benches);
@@ -2,9 +2,10 @@
 //!
 //! At the moment, only UTF-8 encoding is supported.
 
-use crate::{located_err, Error, Located};
+use crate::{located_err, parser::is_line_ending, Error, Located};
 
 pub const START_LINE: usize = 1;
+pub const START_COL: usize = 1;
 
 /// A decoder groups bytes together to be interpreted by an encoding scheme. For
 /// example, for UTF-8, one would group up to 4 bytes together.
@@ -13,7 +14,8 @@ pub trait Decoder<'a>: From<&'a str> + Iterator<Item = Result<&'a [u8], Located<
     /// returns `None`.
     fn peek(&self) -> Option<Result<&'a [u8], Located<Error>>>;
 
-    /// Peeks ahead `len` characters. If the stream has ended, returns `None`.
+    /// Peeks ahead `len` characters. If the stream has ended, or fewer than
+    /// `len` characters remain in the stream, returns `None`.
     fn peek_slice(&self, len: usize) -> Option<Result<&'a [u8], Located<Error>>>;
 
     /// Extracts a slice of bytes from the input stream from the given start
@@ -37,6 +39,7 @@ pub struct Utf8Decoder<'a> {
     pos: usize,
     len: usize,
     line: usize,
+    col: usize,
 }
 
 impl<'a> From<&'a str> for Utf8Decoder<'a> {
@@ -47,6 +50,7 @@ impl<'a> From<&'a str> for Utf8Decoder<'a> {
             pos: 0,
             len: src.len(),
             line: START_LINE,
+            col: START_COL,
         }
     }
 }
@@ -61,13 +65,17 @@ impl<'a> Iterator for Utf8Decoder<'a> {
         let pos = self.pos;
         let b = self.src[pos];
         let ch_len = UTF8_CHAR_WIDTH[b as usize] as usize;
-        if pos + ch_len >= self.len {
-            return Some(located_err(self.line, Error::UnexpectedEof));
+        if pos + ch_len > self.len {
+            return Some(located_err(self.line, self.col, Error::UnexpectedEof));
         }
         self.pos += ch_len;
-        // Exclusively count newline characters as newlines
-        if ch_len == 1 && b == 0x0A {
+        // Count line endings in the same way as the lexer, i.e. "\n", "\r\n"
+        // and a lone "\r" each end a single line
+        if ch_len == 1 && is_line_ending(self.src, pos) {
             self.line += 1;
+            self.col = START_COL;
+        } else {
+            self.col += 1;
         }
         Some(Ok(&self.src[pos..self.pos]))
     }
@@ -81,8 +89,8 @@ impl<'a> Decoder<'a> for Utf8Decoder<'a> {
         }
         let b = self.src[self.pos];
         let ch_len = UTF8_CHAR_WIDTH[b as usize] as usize;
-        if self.pos + ch_len >= self.len {
-            return Some(located_err(self.line, Error::IncompleteUtf8Char));
+        if self.pos + ch_len > self.len {
+            return Some(located_err(self.line, self.col, Error::IncompleteUtf8Char));
         }
         Some(Ok(&self.src[self.pos..self.pos + ch_len]))
     }
@@ -93,11 +101,15 @@ impl<'a> Decoder<'a> for Utf8Decoder<'a> {
             return None;
         }
         let mut bytes_len = 0;
-        for i in 0..len {
-            let pos = (self.pos + bytes_len) as usize;
+        for _ in 0..len {
+            let pos = self.pos + bytes_len;
+            // The stream ended cleanly before we reached `len` characters
+            if pos >= self.len {
+                return None;
+            }
             let ch_len = UTF8_CHAR_WIDTH[self.src[pos] as usize] as usize;
-            if pos + ch_len >= self.len {
-                return Some(located_err(self.line, Error::IncompleteUtf8Char));
+            if pos + ch_len > self.len {
+                return Some(located_err(self.line, self.col, Error::IncompleteUtf8Char));
             }
             bytes_len += ch_len;
         }
@@ -106,7 +118,7 @@ impl<'a> Decoder<'a> for Utf8Decoder<'a> {
 
     #[inline]
     fn slice(&self, start: usize, end: usize) -> Option<&'a [u8]> {
-        if end >= self.len {
+        if end > self.len {
             None
         } else {
             Some(&self.src[start..end])
@@ -150,3 +162,54 @@ const UTF8_CHAR_WIDTH: &[u8; 256] = &[
     3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, // E
     4, 4, 4, 4, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // F
 ];
+
+#[cfg(test)]
+mod test {
+    use alloc::{vec, vec::Vec};
+
+    use super::*;
+
+    #[test]
+    fn peek_slice_past_end() {
+        let mut decoder = Utf8Decoder::from("aé😀");
+        assert_eq!(decoder.next(), Some(Ok("a".as_bytes())));
+        // Only 2 characters remain
+        assert_eq!(decoder.peek_slice(3), None);
+        assert_eq!(decoder.peek_slice(2), Some(Ok("é😀".as_bytes())));
+        assert_eq!(decoder.pos(), 1);
+    }
+
+    #[test]
+    fn peek_slice_final_char() {
+        let mut decoder = Utf8Decoder::from("a😀");
+        assert_eq!(decoder.next(), Some(Ok("a".as_bytes())));
+        assert_eq!(decoder.peek_slice(1), Some(Ok("😀".as_bytes())));
+        assert_eq!(decoder.peek(), Some(Ok("😀".as_bytes())));
+        assert_eq!(decoder.next(), Some(Ok("😀".as_bytes())));
+        assert!(decoder.eof());
+        assert_eq!(decoder.peek_slice(1), None);
+        assert_eq!(decoder.next(), None);
+    }
+
+    #[test]
+    fn line_endings() {
+        let mut decoder = Utf8Decoder::from("a\rb\r\nc\nd");
+        let mut lines = Vec::new();
+        while let Some(ch) = decoder.next() {
+            lines.push((ch.unwrap(), decoder.line()));
+        }
+        assert_eq!(
+            lines,
+            vec![
+                (&b"a"[..], 1),
+                (b"\r", 2),
+                (b"b", 2),
+                (b"\r", 2),
+                (b"\n", 3),
+                (b"c", 3),
+                (b"\n", 4),
+                (b"d", 4),
+            ]
+        );
+    }
+}
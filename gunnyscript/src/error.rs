@@ -1,21 +1,79 @@
 //! Errors produced by the Gunnyscript parser.
 
-use core::str::Utf8Error;
+use alloc::string::String;
+use core::{fmt, str::Utf8Error};
 
+use crate::parser::is_line_ending;
+
+// The width of a tab character when rendering snippets
+const TAB_WIDTH: usize = 4;
+
+/// Associates a value (usually an error) with a position in the source.
+/// Lines and columns are 1-based, and columns are counted in characters.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Located<E> {
     pub line: usize,
+    pub col: usize,
     pub err: E,
 }
 
 impl<E> Located<E> {
-    pub fn new(line: usize, err: E) -> Self {
-        Self { line, err }
+    pub fn new(line: usize, col: usize, err: E) -> Self {
+        Self { line, col, err }
+    }
+
+    /// Renders the line of `src` on which this error occurred, followed by a
+    /// second line containing a caret (`^`) pointing at the column of the
+    /// error. Tabs are expanded to spaces so that the caret lines up with the
+    /// offending character.
+    pub fn snippet(&self, src: &str) -> String {
+        let text = source_line(src, self.line).unwrap_or("");
+        let mut snippet = String::new();
+        let mut width = 0;
+        let mut caret = None;
+        for (i, c) in text.chars().enumerate() {
+            if i + 1 == self.col {
+                caret = Some(width);
+            }
+            if c == '\t' {
+                let spaces = TAB_WIDTH - width % TAB_WIDTH;
+                snippet.extend(core::iter::repeat_n(' ', spaces));
+                width += spaces;
+            } else {
+                snippet.push(c);
+                width += 1;
+            }
+        }
+        // Columns past the end of the line point just after the line
+        let caret = caret.unwrap_or(width);
+        snippet.push('\n');
+        snippet.extend(core::iter::repeat_n(' ', caret));
+        snippet.push('^');
+        snippet
     }
 }
 
-pub fn located_err<T, E>(line: usize, err: E) -> Result<T, Located<E>> {
-    Err(Located::new(line, err))
+pub fn located_err<T, E>(line: usize, col: usize, err: E) -> Result<T, Located<E>> {
+    Err(Located::new(line, col, err))
+}
+
+// Returns the given (1-based) line of `src`, excluding its line ending. Lines
+// are split in the same way as the lexer counts them.
+fn source_line(src: &str, line: usize) -> Option<&str> {
+    let src = src.strip_prefix('\u{FEFF}').unwrap_or(src);
+    let bytes = src.as_bytes();
+    let mut cur = 1;
+    let mut start = 0;
+    for i in 0..bytes.len() {
+        if is_line_ending(bytes, i) {
+            if cur == line {
+                return Some(src[start..i].trim_end_matches('\r'));
+            }
+            cur += 1;
+            start = i + 1;
+        }
+    }
+    (cur == line).then(|| &src[start..])
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -24,13 +82,106 @@ pub enum Error {
     UnexpectedEof,
     UnexpectedChar,
     Utf8Error(Utf8Error),
-    InvalidPropertyId,
-    StringLiteralDelimTooLong { len: usize, max_len: usize },
+    /// A property ID contained a character that is not allowed. The error is
+    /// located at the start of the property ID, while `col` is the (1-based)
+    /// column of the offending character within the ID. `ch` is `None` if the
+    /// property ID is empty.
+    InvalidPropertyId {
+        ch: Option<char>,
+        col: usize,
+    },
+    StringLiteralDelimTooLong {
+        len: usize,
+        max_len: usize,
+    },
     MissingTerminator,
+    /// A regular string was not terminated. Located at the line on which the
+    /// string was opened.
+    UnterminatedString,
+    /// A literal string was not terminated. Located at the line on which the
+    /// string was opened.
+    UnterminatedLiteralString,
+    /// A block comment was not terminated. Located at the line on which the
+    /// comment was opened.
+    UnterminatedBlockComment,
+    UnexpectedToken,
+    InvalidNumber,
+    /// A regular string contained an invalid escape sequence (see
+    /// [`crate::unescape_regular_string`]).
+    InvalidEscape,
+    InvalidDate,
+    InvalidDateTime,
+    /// A property ID was used more than once in the same object. The error is
+    /// located at the second occurrence, and `id_line` refers to the line on
+    /// which the property was first defined.
+    DuplicatePropertyId {
+        id_line: usize,
+    },
+    /// Objects and arrays were nested more deeply than the parser allows. The
+    /// error is located at the opening of the object or array that exceeded
+    /// the `limit`.
+    MaxDepthExceeded {
+        depth: usize,
+        limit: usize,
+    },
+    /// The lexer attempted to advance past a peek taken from a position other
+    /// than its current one. This indicates a bug in the lexer.
+    InvalidPeek {
+        from: usize,
+        pos: usize,
+    },
+    /// A float to be written was infinite or NaN, neither of which can be
+    /// represented in Gunnyscript.
+    NonFiniteNumber,
+    /// The destination to which a document was being written failed.
+    Fmt(fmt::Error),
 }
 
 impl Error {
-    pub fn located(self, line: usize) -> Located<Self> {
-        Located { line, err: self }
+    pub fn located(self, line: usize, col: usize) -> Located<Self> {
+        Located::new(line, col, self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Lexer, Parser, Token};
+    use alloc::vec::Vec;
+
+    fn lex_err(src: &str) -> Located<Error> {
+        Lexer::from(src)
+            .collect::<Result<Vec<Token>, Located<Error>>>()
+            .unwrap_err()
+    }
+
+    #[test]
+    fn snippet() {
+        const TEST_CASES: &[(&str, &str)] = &[
+            // Error in the middle of a line
+            ("{\n  title $\n}\n", "  title $\n        ^"),
+            // Error on the last line, which has no trailing newline
+            ("{\n  title true\n$", "$\n^"),
+            // Error in the first column
+            ("{\n$ title true\n}", "$ title true\n^"),
+            // Tabs are expanded for alignment
+            ("{\n\tid\t$\n}", "    id  $\n        ^"),
+            // Windows line endings
+            ("{\r\n  title $\r\n}", "  title $\n        ^"),
+            // Multi-byte characters count as a single column
+            ("{ title \"😂\" $ }", "{ title \"😂\" $ }\n            ^"),
+        ];
+        for (src, expected) in TEST_CASES {
+            let err = lex_err(src);
+            assert_eq!(err.snippet(src), *expected, "test case: {:?}", src);
+        }
+    }
+
+    #[test]
+    fn snippet_past_end_of_line() {
+        let src = "{ a true";
+        let err = Parser::from(src).parse().unwrap_err();
+        assert_eq!(err, Located::new(1, 9, Error::UnexpectedEof));
+        assert_eq!(err.snippet(src), "{ a true\n        ^");
     }
 }
@@ -13,13 +13,27 @@
 
 extern crate alloc;
 
+// Not used by the lexer yet, but compiled so that it stays up to date
+#[allow(dead_code)]
+mod encoding;
 mod error;
+mod number;
 mod parser;
 mod value;
+mod writer;
 
 pub use error::{located_err, Error, Located};
-pub use parser::{Lexer, SimpleValue, Token};
+pub use number::{LocaleOptions, Number};
+pub use parser::{
+    unescape_regular_string, Lexer, LexerOptions, Parser, ParserOptions, SimpleValue, Spanned,
+    SpannedLexer, Token, DEFAULT_MAX_DEPTH,
+};
 pub use value::{
-    Date, DateTime, Document, DocumentedProperties, DocumentedProperty, MaybeLiteralString,
-    MultiLineString, Number, Value, ValueString,
+    collect_docstrings, dedent, join_docstring, Date, DateTime, DateTimeParseOptions, Document,
+    DocumentedProperties, DocumentedProperty, MaybeLiteralString, MultiLineString, Value,
+    ValueString,
+};
+pub use writer::{
+    choose_literal_hashes, escape_regular_string, quote_string, to_string, write_document,
+    WriterOptions,
 };
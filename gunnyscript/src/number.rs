@@ -0,0 +1,485 @@
+//! Numeric values in Gunnyscript.
+
+use alloc::string::String;
+use core::{fmt, str::FromStr};
+
+use crate::Error;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Number {
+    Float(f64),
+    Unsigned(u64),
+    Signed(i64),
+}
+
+impl Number {
+    /// Returns whether this number is an integer. Floats are considered to be
+    /// integers if they are finite and have no fractional part.
+    pub fn is_integer(&self) -> bool {
+        match self {
+            Self::Float(_) => self.fractional_part() == Some(0.0),
+            Self::Unsigned(_) | Self::Signed(_) => true,
+        }
+    }
+
+    /// Returns whether this number is strictly less than zero.
+    pub fn is_negative(&self) -> bool {
+        match self {
+            Self::Float(f) => *f < 0.0,
+            Self::Unsigned(_) => false,
+            Self::Signed(i) => *i < 0,
+        }
+    }
+
+    /// Returns whether this number is zero (including negative zero).
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Self::Float(f) => *f == 0.0,
+            Self::Unsigned(u) => *u == 0,
+            Self::Signed(i) => *i == 0,
+        }
+    }
+
+    /// Compares two numbers by their numeric value, regardless of their
+    /// variants (unlike the derived `PartialEq`, which considers
+    /// `Number::Unsigned(1)` and `Number::Signed(1)` to be different).
+    ///
+    /// Integers are compared exactly. An integer is only equal to a float if
+    /// the float is integral and has exactly the same value, which avoids
+    /// large integers comparing equal to the nearest representable float.
+    pub fn value_eq(&self, other: &Number) -> bool {
+        match (self.as_i128(), other.as_i128()) {
+            (Some(a), Some(b)) => a == b,
+            (Some(i), None) => other.float_eq_int(i),
+            (None, Some(i)) => self.float_eq_int(i),
+            (None, None) => self.as_f64() == other.as_f64(),
+        }
+    }
+
+    /// Parses a number formatted according to the given locale options, e.g.
+    /// `$1,234.50` or `1 234,50`. Leading and trailing whitespace, as well as
+    /// the currency symbol (if any) before or after the number, are removed.
+    /// A minus sign may precede the currency symbol.
+    ///
+    /// Thousands separators may only appear in the integral part of the
+    /// number, and must separate groups of exactly 3 digits. The resulting
+    /// number is then parsed as per [`Number::from_str`].
+    pub fn parse_locale(s: &str, opts: LocaleOptions) -> Result<Self, Error> {
+        if opts.thousands_separator == Some(opts.decimal_separator) {
+            return Err(Error::InvalidNumber);
+        }
+        let s = s.trim();
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        let s = match opts.currency_symbol {
+            Some(sym) if !sym.is_empty() => s
+                .strip_prefix(sym)
+                .or_else(|| s.strip_suffix(sym))
+                .unwrap_or(s)
+                .trim(),
+            _ => s,
+        };
+
+        let (int_part, frac_part) = match s.split_once(opts.decimal_separator) {
+            Some((int_part, frac_part)) => (int_part, Some(frac_part)),
+            None => (s, None),
+        };
+        let mut normalized = String::with_capacity(s.len() + 1);
+        if negative {
+            normalized.push('-');
+        }
+        match opts.thousands_separator {
+            Some(sep) if int_part.contains(sep) => {
+                for (i, group) in int_part.split(sep).enumerate() {
+                    let valid_len = if i == 0 {
+                        (1..=3).contains(&group.len())
+                    } else {
+                        group.len() == 3
+                    };
+                    if !valid_len {
+                        return Err(Error::InvalidNumber);
+                    }
+                    normalized.push_str(group);
+                }
+            }
+            _ => normalized.push_str(int_part),
+        }
+        if let Some(frac_part) = frac_part {
+            normalized.push('.');
+            normalized.push_str(frac_part);
+        }
+        normalized.parse()
+    }
+
+    // Returns integer variants as an i128, which can hold both u64 and i64.
+    fn as_i128(&self) -> Option<i128> {
+        match self {
+            Self::Float(_) => None,
+            Self::Unsigned(u) => Some(*u as i128),
+            Self::Signed(i) => Some(*i as i128),
+        }
+    }
+
+    fn as_f64(&self) -> f64 {
+        match self {
+            Self::Float(f) => *f,
+            Self::Unsigned(u) => *u as f64,
+            Self::Signed(i) => *i as f64,
+        }
+    }
+
+    // Returns whether this (float) number is exactly equal to the given
+    // integer.
+    fn float_eq_int(&self, i: i128) -> bool {
+        match self {
+            // All integers within the range of u64/i64 fit within (-2^64, 2^64),
+            // where an f64 can be converted to an i128 without saturating.
+            Self::Float(f) if self.fractional_part() == Some(0.0) && f.abs() < U64_RANGE_F64 => {
+                *f as i128 == i
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the fractional part of a float, carrying the sign of the float.
+    /// Returns `None` for integers, as well as for infinite and NaN floats.
+    pub fn fractional_part(&self) -> Option<f64> {
+        match self {
+            Self::Float(f) if f.is_finite() => {
+                // Floats this large cannot represent fractions
+                if f.abs() >= MIN_INTEGRAL_F64 {
+                    Some(0.0)
+                } else {
+                    Some(f - (*f as i64) as f64)
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Options to control how [`Number::parse_locale`] interprets numbers. The
+/// defaults correspond to Gunnyscript's own number format.
+#[derive(Debug, Clone)]
+pub struct LocaleOptions<'a> {
+    /// A currency symbol (e.g. `$` or `€`) that may precede or follow the
+    /// number.
+    pub currency_symbol: Option<&'a str>,
+    /// The character used to group thousands in the integral part of the
+    /// number (e.g. `,` in the US or a space in much of Europe).
+    pub thousands_separator: Option<char>,
+    /// The character separating the integral and fractional parts of the
+    /// number.
+    pub decimal_separator: char,
+}
+
+impl Default for LocaleOptions<'_> {
+    fn default() -> Self {
+        Self {
+            currency_symbol: None,
+            thousands_separator: None,
+            decimal_separator: '.',
+        }
+    }
+}
+
+// The smallest magnitude at which all 64-bit floats are integers (2^52).
+const MIN_INTEGRAL_F64: f64 = 4_503_599_627_370_496.0;
+// 2^64
+const U64_RANGE_F64: f64 = 18_446_744_073_709_551_616.0;
+
+impl fmt::Display for Number {
+    /// Formats the number in its canonical Gunnyscript form. Integers are
+    /// written in plain decimal. Floats are written using the shortest
+    /// representation that reads back as the same value, and always include a
+    /// fractional part or exponent (e.g. `2.0` rather than `2`) so that they
+    /// are read back as floats.
+    ///
+    /// Infinite and NaN floats have no Gunnyscript representation, and are
+    /// written as `inf`, `-inf` and `NaN` respectively.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // Debug formatting always includes a fractional part or exponent
+            Self::Float(v) => write!(f, "{:?}", v),
+            Self::Unsigned(u) => write!(f, "{}", u),
+            Self::Signed(i) => write!(f, "{}", i),
+        }
+    }
+}
+
+impl FromStr for Number {
+    type Err = Error;
+
+    /// Parses a number from its Gunnyscript representation. Numbers with a
+    /// fractional part or an exponent are parsed as floats, negative integers
+    /// as signed integers and all other integers as unsigned integers. A
+    /// decimal point must be followed by at least one digit, and floats that
+    /// are too large to be represented (e.g. `1e400`) are rejected.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s.strip_prefix('-').unwrap_or(s);
+        if !digits.as_bytes().first().is_some_and(u8::is_ascii_digit)
+            || !digits
+                .bytes()
+                .all(|b| matches!(b, b'0'..=b'9' | b'.' | b'e' | b'E' | b'+' | b'-'))
+            || digits
+                .split('.')
+                .skip(1)
+                .any(|frac| !frac.as_bytes().first().is_some_and(u8::is_ascii_digit))
+        {
+            return Err(Error::InvalidNumber);
+        }
+        if digits.contains(['.', 'e', 'E']) {
+            s.parse::<f64>()
+                .ok()
+                .filter(|f| f.is_finite())
+                .map(Self::Float)
+                .ok_or(Error::InvalidNumber)
+        } else if s.starts_with('-') {
+            s.parse()
+                .map(Self::Signed)
+                .map_err(|_| Error::InvalidNumber)
+        } else {
+            s.parse()
+                .map(Self::Unsigned)
+                .map_err(|_| Error::InvalidNumber)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::string::ToString;
+
+    use super::*;
+
+    #[test]
+    fn number_parsing() {
+        const TEST_CASES: &[(&str, Number)] = &[
+            ("0", Number::Unsigned(0)),
+            ("42", Number::Unsigned(42)),
+            ("18446744073709551615", Number::Unsigned(u64::MAX)),
+            ("-1", Number::Signed(-1)),
+            ("-9223372036854775808", Number::Signed(i64::MIN)),
+            ("1.5", Number::Float(1.5)),
+            ("-0.25", Number::Float(-0.25)),
+            ("1e3", Number::Float(1000.0)),
+            ("2.5E-1", Number::Float(0.25)),
+            ("1e308", Number::Float(1e308)),
+            ("1e-400", Number::Float(0.0)),
+        ];
+        for (tc, expected) in TEST_CASES {
+            assert_eq!(
+                tc.parse::<Number>().unwrap(),
+                *expected,
+                "test case: {}",
+                tc
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_numbers() {
+        const TEST_CASES: &[&str] = &[
+            "",
+            "-",
+            "+1",
+            "inf",
+            "NaN",
+            "1.2.3",
+            "1_000",
+            "18446744073709551616",
+            "-9223372036854775809",
+            "1.",
+            "-1.",
+            "1.e3",
+            "1e400",
+            "-1e400",
+        ];
+        for tc in TEST_CASES {
+            assert_eq!(
+                tc.parse::<Number>(),
+                Err(Error::InvalidNumber),
+                "test case: {}",
+                tc
+            );
+        }
+    }
+
+    #[test]
+    fn locale_number_parsing() {
+        let us = LocaleOptions {
+            currency_symbol: Some("$"),
+            thousands_separator: Some(','),
+            decimal_separator: '.',
+        };
+        let eu = LocaleOptions {
+            currency_symbol: Some("€"),
+            thousands_separator: Some(' '),
+            decimal_separator: ',',
+        };
+        const US: &[(&str, Number)] = &[
+            ("$1,234.50", Number::Float(1234.5)),
+            ("-$1,234.50", Number::Float(-1234.5)),
+            (" $1,234,567 ", Number::Unsigned(1_234_567)),
+            ("1234", Number::Unsigned(1234)),
+            ("$-12", Number::Signed(-12)),
+            ("0.5$", Number::Float(0.5)),
+        ];
+        const EU: &[(&str, Number)] = &[
+            ("1 234,50", Number::Float(1234.5)),
+            ("1 234,50 €", Number::Float(1234.5)),
+            ("€12", Number::Unsigned(12)),
+            ("-1 000 000", Number::Signed(-1_000_000)),
+        ];
+        for (tc, expected) in US {
+            assert_eq!(
+                Number::parse_locale(tc, us.clone()).unwrap(),
+                *expected,
+                "test case: {}",
+                tc
+            );
+        }
+        for (tc, expected) in EU {
+            assert_eq!(
+                Number::parse_locale(tc, eu.clone()).unwrap(),
+                *expected,
+                "test case: {}",
+                tc
+            );
+        }
+
+        const INVALID_US: &[&str] = &["1,23", "1,2345", ",123", "1.234,5", "$", "£12", "1,,234"];
+        for tc in INVALID_US {
+            assert_eq!(
+                Number::parse_locale(tc, us.clone()),
+                Err(Error::InvalidNumber),
+                "test case: {}",
+                tc
+            );
+        }
+        assert_eq!(
+            Number::parse_locale("1 234.50", eu),
+            Err(Error::InvalidNumber)
+        );
+
+        // The defaults are equivalent to strict parsing
+        assert_eq!(
+            Number::parse_locale("-2.5e3", LocaleOptions::default()),
+            Ok(Number::Float(-2500.0))
+        );
+        assert_eq!(
+            Number::parse_locale("1,234", LocaleOptions::default()),
+            Err(Error::InvalidNumber)
+        );
+        // Strict parsing is unaffected
+        assert_eq!("$1,234.50".parse::<Number>(), Err(Error::InvalidNumber));
+    }
+
+    #[test]
+    fn number_formatting() {
+        const TEST_CASES: &[(Number, &str)] = &[
+            (Number::Unsigned(0), "0"),
+            (Number::Unsigned(u64::MAX), "18446744073709551615"),
+            (Number::Signed(-42), "-42"),
+            (Number::Signed(i64::MIN), "-9223372036854775808"),
+            (Number::Float(2.0), "2.0"),
+            (Number::Float(-0.0), "-0.0"),
+            (Number::Float(1.5), "1.5"),
+            (Number::Float(0.1 + 0.2), "0.30000000000000004"),
+            (Number::Float(1.0 / 3.0), "0.3333333333333333"),
+            (Number::Float(1e300), "1e300"),
+            (Number::Float(f64::INFINITY), "inf"),
+        ];
+        for (n, expected) in TEST_CASES {
+            let s = n.to_string();
+            assert_eq!(s, *expected, "number: {:?}", n);
+            if n.fractional_part().is_some() || n.is_integer() {
+                // Finite numbers read back as the same variant and value
+                assert_eq!(s.parse::<Number>().unwrap(), *n, "number: {:?}", n);
+            }
+        }
+    }
+
+    #[test]
+    fn predicates() {
+        // (number, is_integer, is_negative, is_zero, fractional_part)
+        const TEST_CASES: &[(Number, bool, bool, bool, Option<f64>)] = &[
+            (Number::Unsigned(0), true, false, true, None),
+            (Number::Unsigned(42), true, false, false, None),
+            (Number::Signed(0), true, false, true, None),
+            (Number::Signed(-3), true, true, false, None),
+            (Number::Signed(3), true, false, false, None),
+            (Number::Float(0.0), true, false, true, Some(0.0)),
+            (Number::Float(-0.0), true, false, true, Some(0.0)),
+            (Number::Float(2.0), true, false, false, Some(0.0)),
+            (Number::Float(-2.0), true, true, false, Some(0.0)),
+            (Number::Float(2.5), false, false, false, Some(0.5)),
+            (Number::Float(-2.25), false, true, false, Some(-0.25)),
+            (Number::Float(1e300), true, false, false, Some(0.0)),
+            (Number::Float(f64::INFINITY), false, false, false, None),
+            (Number::Float(f64::NEG_INFINITY), false, true, false, None),
+            (Number::Float(f64::NAN), false, false, false, None),
+        ];
+        for (n, is_integer, is_negative, is_zero, fractional_part) in TEST_CASES {
+            assert_eq!(n.is_integer(), *is_integer, "is_integer: {:?}", n);
+            assert_eq!(n.is_negative(), *is_negative, "is_negative: {:?}", n);
+            assert_eq!(n.is_zero(), *is_zero, "is_zero: {:?}", n);
+            assert_eq!(
+                n.fractional_part(),
+                *fractional_part,
+                "fractional_part: {:?}",
+                n
+            );
+        }
+    }
+
+    #[test]
+    fn value_equality() {
+        const EQUAL: &[(Number, Number)] = &[
+            (Number::Unsigned(1), Number::Signed(1)),
+            (Number::Unsigned(1), Number::Float(1.0)),
+            (Number::Signed(-2), Number::Float(-2.0)),
+            (Number::Unsigned(0), Number::Float(-0.0)),
+            (Number::Float(0.5), Number::Float(0.5)),
+            (Number::Unsigned(u64::MAX), Number::Unsigned(u64::MAX)),
+            (
+                Number::Signed(i64::MIN),
+                Number::Float(-9_223_372_036_854_775_808.0),
+            ),
+            (
+                Number::Unsigned(1 << 63),
+                Number::Float(9_223_372_036_854_775_808.0),
+            ),
+        ];
+        for (a, b) in EQUAL {
+            assert!(a.value_eq(b), "{:?} == {:?}", a, b);
+            assert!(b.value_eq(a), "{:?} == {:?}", b, a);
+        }
+
+        const NOT_EQUAL: &[(Number, Number)] = &[
+            (Number::Unsigned(1), Number::Signed(-1)),
+            (Number::Unsigned(1), Number::Float(1.5)),
+            (Number::Signed(-1), Number::Float(1.0)),
+            (Number::Float(0.1), Number::Float(0.2)),
+            (Number::Float(f64::NAN), Number::Float(f64::NAN)),
+            (Number::Unsigned(0), Number::Float(f64::NAN)),
+            // u64::MAX is not exactly representable as a float, and the
+            // nearest float (2^64) must not compare equal to it
+            (
+                Number::Unsigned(u64::MAX),
+                Number::Float(18_446_744_073_709_551_616.0),
+            ),
+            (Number::Unsigned(u64::MAX), Number::Signed(-1)),
+            (Number::Signed(i64::MAX), Number::Float(f64::INFINITY)),
+        ];
+        for (a, b) in NOT_EQUAL {
+            assert!(!a.value_eq(b), "{:?} != {:?}", a, b);
+            assert!(!b.value_eq(a), "{:?} != {:?}", b, a);
+        }
+
+        // The derived equality remains variant-sensitive
+        assert_ne!(Number::Unsigned(1), Number::Signed(1));
+    }
+}
@@ -2,11 +2,26 @@
 //!
 //! Only supports UTF-8 encoding at present.
 
-use crate::{located_err, Error, Located};
+use alloc::{borrow::Cow, collections::BTreeMap, string::String, vec::Vec};
+use core::ops::Range;
+
+use crate::{
+    located_err, Document, DocumentedProperties, DocumentedProperty, Error, Located,
+    MaybeLiteralString, MultiLineString, Value, ValueString,
+};
 
 const START_LINE: usize = 1;
-const MATCH_BUF_SIZE: usize = 10;
 const STRING_LITERAL_DELIM: &[u8] = b"\"####################";
+// The maximum number of `#` characters delimiting a literal string
+pub(crate) const MAX_LITERAL_HASHES: usize = STRING_LITERAL_DELIM.len() - 1;
+// Must be large enough to hold the longest string literal delimiter
+const MATCH_BUF_SIZE: usize = STRING_LITERAL_DELIM.len();
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+// Property IDs, numbers, dates and date/times run until one of these (or the
+// end of the input)
+const WORD_TERMINATORS: &[&[u8]] = &[
+    b" ", b"\n", b"\t", b"\r", b"{", b"}", b"[", b"]", b",", b"//", b"/*",
+];
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token<'a> {
@@ -15,7 +30,12 @@ pub enum Token<'a> {
     ObjectEnd,
     ArrayStart,
     ArrayEnd,
+    /// Separates array elements or object properties.
+    Comma,
     PropertyId(&'a str),
+    /// A `@`-prefixed identifier (without the `@`). Only produced if enabled
+    /// through [`LexerOptions::allow_directives`].
+    Directive(&'a str),
     Value(SimpleValue<'a>),
 }
 
@@ -32,23 +52,95 @@ pub enum SimpleValue<'a> {
     DedentLiteralString(&'a str),
 }
 
+/// Options to control the behaviour of the [`Lexer`].
+#[derive(Debug, Clone, Default)]
+pub struct LexerOptions {
+    /// If set, `@`-prefixed identifiers are lexed as [`Token::Directive`]s.
+    /// Otherwise they result in an [`Error::UnexpectedChar`].
+    ///
+    /// Directives are only surfaced by the lexer, for consumers that handle
+    /// them separately from a document's data. The [`Parser`] does not accept
+    /// them anywhere in a document, and reports them as an
+    /// [`Error::UnexpectedToken`] if enabled.
+    pub allow_directives: bool,
+    /// If set, single-quoted strings (e.g. `'hello'`, with `\'` escaping a
+    /// quote) are lexed as regular strings, equivalent to double-quoted ones.
+    /// Otherwise `'` results in an [`Error::UnexpectedChar`].
+    pub single_quoted_strings: bool,
+}
+
 pub struct Lexer<'a> {
+    text: &'a str, // The original input, which is already valid UTF-8
     src: &'a [u8],
     len: usize,  // Memoized input length
     pos: usize,  // Our current position in the input
     line: usize, // Our current line number
+    // The position and line at which the most recent token started
+    token_pos: usize,
+    token_line: usize,
+    opts: LexerOptions,
 }
 
 impl<'a> From<&'a str> for Lexer<'a> {
     fn from(s: &'a str) -> Self {
+        Self::new(s, LexerOptions::default())
+    }
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(s: &'a str, opts: LexerOptions) -> Self {
         let src = s.as_bytes();
+        // Skip a leading byte order mark, if any. A BOM anywhere else in the
+        // input is treated as an unexpected character.
+        let pos = if src.starts_with(UTF8_BOM) {
+            UTF8_BOM.len()
+        } else {
+            0
+        };
         Self {
+            text: s,
             src,
             len: src.len(),
-            pos: 0,
+            pos,
             line: START_LINE,
+            token_pos: pos,
+            token_line: START_LINE,
+            opts,
         }
     }
+
+    /// Converts this lexer into one that yields tokens along with their
+    /// spans in the source.
+    pub fn spanned(self) -> SpannedLexer<'a> {
+        SpannedLexer { lexer: self }
+    }
+}
+
+/// Associates a value (usually a [`Token`]) with its byte range in the source.
+/// For tokens, the span covers the full text of the token, including any
+/// delimiters (e.g. the quotes around a string or the `///` preceding a
+/// docstring line).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned<T> {
+    pub inner: T,
+    pub span: Range<usize>,
+}
+
+/// A lexer that yields tokens along with their spans. See [`Lexer::spanned`].
+pub struct SpannedLexer<'a> {
+    lexer: Lexer<'a>,
+}
+
+impl<'a> Iterator for SpannedLexer<'a> {
+    type Item = Result<Spanned<Token<'a>>, Located<Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = self.lexer.next()?;
+        Some(token.map(|inner| Spanned {
+            inner,
+            span: self.lexer.token_pos..self.lexer.pos,
+        }))
+    }
 }
 
 impl<'a> Iterator for Lexer<'a> {
@@ -56,6 +148,8 @@ impl<'a> Iterator for Lexer<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         while self.pos < self.len {
+            self.token_pos = self.pos;
+            self.token_line = self.line;
             let peek = match self.peek_char() {
                 Ok(p) => p,
                 Err(e) => return Some(Err(e)),
@@ -66,7 +160,11 @@ impl<'a> Iterator for Lexer<'a> {
             }
             match peek.slice[0] {
                 // Whitespace
-                b' ' | b'\t' | b'\r' | b'\n' => self.advance(peek),
+                b' ' | b'\t' | b'\r' | b'\n' => {
+                    if let Err(e) = self.advance(peek) {
+                        return Some(Err(e));
+                    }
+                }
                 b'/' => match self.try_parse_comment_or_docstring() {
                     Ok(Some(docstring)) => return Some(Ok(docstring)),
                     // Skip comments that aren't docstrings
@@ -88,6 +186,9 @@ impl<'a> Iterator for Lexer<'a> {
                     }
                 }
                 b'"' => return Some(self.parse_string()),
+                b'\'' if self.opts.single_quoted_strings => {
+                    return Some(self.parse_single_quoted_string())
+                }
                 b'#' => return Some(self.parse_string_literal()),
                 b'd' => {
                     return match self.try_parse_dedent_string() {
@@ -97,11 +198,21 @@ impl<'a> Iterator for Lexer<'a> {
                     }
                 }
                 b'a'..=b'z' | b'A'..=b'Z' | b'_' => return Some(self.parse_property_id()),
-                b'{' => return Some(Ok(Token::ObjectStart)),
-                b'}' => return Some(Ok(Token::ObjectEnd)),
-                b'[' => return Some(Ok(Token::ArrayStart)),
-                b']' => return Some(Ok(Token::ArrayEnd)),
-                b'0'..=b'9' => {
+                b'@' if self.opts.allow_directives => return Some(self.parse_directive()),
+                b'{' | b'}' | b'[' | b']' | b',' => {
+                    let b = peek.slice[0];
+                    if let Err(e) = self.advance(peek) {
+                        return Some(Err(e));
+                    }
+                    return Some(Ok(match b {
+                        b'{' => Token::ObjectStart,
+                        b'}' => Token::ObjectEnd,
+                        b'[' => Token::ArrayStart,
+                        b']' => Token::ArrayEnd,
+                        _ => Token::Comma,
+                    }));
+                }
+                b'0'..=b'9' | b'-' => {
                     return match self.try_parse_number() {
                         Ok(Some(t)) => Some(Ok(t)),
                         Ok(None) => match self.try_parse_datetime() {
@@ -120,15 +231,19 @@ impl<'a> Iterator for Lexer<'a> {
 }
 
 impl<'a> Lexer<'a> {
-    fn advance(&mut self, peek: Peek<'a>) {
+    // Advances past the given peek, which must have been taken from our
+    // current position. A stale peek indicates a bug in the lexer, which is
+    // reported as an error rather than panicking.
+    fn advance(&mut self, peek: Peek<'a>) -> Result<(), Located<Error>> {
         if peek.from != self.pos {
-            panic!(
-                "unexpected starting position for peek advancement: from={}, pos={}",
-                peek.from, self.pos
-            );
+            return self.located_err(Error::InvalidPeek {
+                from: peek.from,
+                pos: self.pos,
+            });
         }
         self.pos += peek.slice.len();
         self.line += peek.lines;
+        Ok(())
     }
 
     fn try_parse_comment_or_docstring(&mut self) -> Result<Option<Token<'a>>, Located<Error>> {
@@ -136,62 +251,79 @@ impl<'a> Lexer<'a> {
         if peek.starts_with(b"///") {
             // Skip past the "///" - we're only interested in the rest of the
             // line, including the newline.
-            self.advance(peek);
+            self.advance(peek)?;
 
-            let peek = self.peek_until_match(&[b"\n"], &[], true, false)?;
-            let s = core::str::from_utf8(peek.slice)
-                .map_err(|e| Located::new(self.line, Error::Utf8Error(e)))?;
-            self.advance(peek);
+            let peek = self.peek_line()?;
+            let s = self.peek_str(&peek)?;
+            self.advance(peek)?;
             return Ok(Some(Token::DocstringLine(s)));
         }
         let peek = if peek.starts_with(b"/*") {
-            self.peek_until_match(&[b"*/"], &[], true, true)?
+            self.peek_until_match(&[b"*/"], true, Some(Error::UnterminatedBlockComment))?
         } else if peek.starts_with(b"//") {
-            self.peek_until_match(&[b"\n"], &[], true, false)?
+            self.peek_line()?
         } else {
             return self.located_err(Error::UnexpectedChar);
         };
-        self.advance(peek);
+        self.advance(peek)?;
         // Skip comments
         Ok(None)
     }
 
     fn try_parse_null(&mut self) -> Result<Option<Token<'a>>, Located<Error>> {
-        let peek = self.peek_to_len(4)?;
-        if peek.slice == b"null" {
-            self.advance(peek);
-            Ok(Some(Token::Value(SimpleValue::Null)))
-        } else {
-            Ok(None)
-        }
+        self.try_parse_keyword(b"null", SimpleValue::Null)
     }
 
     fn try_parse_bool(&mut self, first: u8) -> Result<Option<Token<'a>>, Located<Error>> {
         if first == b't' {
-            let peek = self.peek_to_len(4)?;
-            if peek.slice == b"true" {
-                self.advance(peek);
-                return Ok(Some(Token::Value(SimpleValue::Bool(true))));
-            }
+            self.try_parse_keyword(b"true", SimpleValue::Bool(true))
         } else {
-            let peek = self.peek_to_len(5)?;
-            if peek.slice == b"false" {
-                self.advance(peek);
-                return Ok(Some(Token::Value(SimpleValue::Bool(false))));
-            }
-        };
-        Ok(None)
+            self.try_parse_keyword(b"false", SimpleValue::Bool(false))
+        }
+    }
+
+    // Lexes the given keyword as the given value, provided that it's a whole
+    // word. Otherwise (e.g. for `nullable`) it's the start of a property ID.
+    fn try_parse_keyword(
+        &mut self,
+        keyword: &[u8],
+        value: SimpleValue<'a>,
+    ) -> Result<Option<Token<'a>>, Located<Error>> {
+        let peek = self.peek_to_len(keyword.len())?;
+        let rest = &self.src[self.pos + peek.slice.len()..];
+        let terminated = rest.is_empty() || WORD_TERMINATORS.iter().any(|t| rest.starts_with(t));
+        if peek.slice == keyword && terminated {
+            self.advance(peek)?;
+            Ok(Some(Token::Value(value)))
+        } else {
+            Ok(None)
+        }
     }
 
     fn parse_property_id(&mut self) -> Result<Token<'a>, Located<Error>> {
-        let peek = self.peek_until_match(&[b" ", b"\n", b"\t", b"\r"], &[], false, false)?;
-        if !is_valid_property_id(peek.slice) {
-            return self.located_err(Error::InvalidPropertyId);
+        let id = self._parse_property_id(0)?;
+        Ok(Token::PropertyId(id))
+    }
+
+    fn _parse_property_id(&mut self, skip_initial: usize) -> Result<&'a str, Located<Error>> {
+        self.pos += skip_initial;
+        let peek = self.peek_word()?;
+        if let Some(i) = invalid_property_id_pos(peek.slice) {
+            // All valid property ID characters are ASCII, so `i` is both a
+            // character boundary and the character offset into the ID
+            let ch = self.text[self.pos + i..].chars().next();
+            let ch = ch.filter(|_| i < peek.slice.len());
+            return self.located_err(Error::InvalidPropertyId { ch, col: i + 1 });
         }
-        let s = core::str::from_utf8(peek.slice)
-            .map_err(|e| Located::new(self.line, Error::Utf8Error(e)))?;
-        self.advance(peek);
-        Ok(Token::PropertyId(s))
+        let s = self.peek_str(&peek)?;
+        self.advance(peek)?;
+        Ok(s)
+    }
+
+    fn parse_directive(&mut self) -> Result<Token<'a>, Located<Error>> {
+        // Skip over the initial '@' character
+        let id = self._parse_property_id(1)?;
+        Ok(Token::Directive(id))
     }
 
     fn parse_string(&mut self) -> Result<Token<'a>, Located<Error>> {
@@ -199,14 +331,26 @@ impl<'a> Lexer<'a> {
         Ok(Token::Value(SimpleValue::String(s)))
     }
 
+    fn parse_single_quoted_string(&mut self) -> Result<Token<'a>, Located<Error>> {
+        let s = self._parse_quoted_string(1, b'\'')?;
+        Ok(Token::Value(SimpleValue::String(s)))
+    }
+
     fn _parse_string(&mut self, skip_initial: usize) -> Result<&'a str, Located<Error>> {
-        // Skip over the initial '"' character
+        self._parse_quoted_string(skip_initial, b'"')
+    }
+
+    fn _parse_quoted_string(
+        &mut self,
+        skip_initial: usize,
+        quote: u8,
+    ) -> Result<&'a str, Located<Error>> {
+        // Skip over the initial quote character
         self.pos += skip_initial;
-        let peek = self.peek_until_match(&[b"\""], &[b"\\\""], false, true)?;
-        let s = core::str::from_utf8(peek.slice)
-            .map_err(|e| Located::new(self.line, Error::Utf8Error(e)))?;
-        self.advance(peek);
-        // Skip over the terminating '"' character
+        let peek = self.peek_until_quote(quote)?;
+        let s = self.peek_str(&peek)?;
+        self.advance(peek)?;
+        // Skip over the terminating quote character
         self.pos += 1;
         Ok(s)
     }
@@ -216,30 +360,39 @@ impl<'a> Lexer<'a> {
         Ok(Token::Value(SimpleValue::LiteralString(s)))
     }
 
+    // Parses a literal string delimited by N '#' characters (1 <= N <= 20)
+    // followed by '"', e.g. `##"content"##`. The string is terminated by the
+    // first occurrence of '"' followed by exactly N '#' characters. The content
+    // may therefore contain '"' followed by fewer than N '#' characters (e.g.
+    // `"#` within `##"..."##`). A '"' followed by N or more '#' characters
+    // always terminates the string, so such content requires a longer
+    // delimiter.
     fn _parse_string_literal(&mut self, skip_initial: usize) -> Result<&'a str, Located<Error>> {
         self.pos += skip_initial;
-        let peek = self.peek_until_not_match(&[b"#"])?;
+        let peek = self.peek_until_not_match(&[b"#"], Error::UnterminatedLiteralString)?;
         let hash_count = peek.slice.len();
-        if hash_count > STRING_LITERAL_DELIM.len() - 1 {
+        if hash_count > MAX_LITERAL_HASHES {
             return self.located_err(Error::StringLiteralDelimTooLong {
                 len: hash_count,
-                max_len: STRING_LITERAL_DELIM.len() - 1,
+                max_len: MAX_LITERAL_HASHES,
             });
         }
-        self.advance(peek);
+        self.advance(peek)?;
 
         // We expect a '"' character after '#'*
         let peek = self.peek_char()?;
         if peek.slice != b"\"" {
             return self.located_err(Error::UnexpectedChar);
         }
-        self.advance(peek);
+        self.advance(peek)?;
 
-        let peek =
-            self.peek_until_match(&[&STRING_LITERAL_DELIM[..hash_count + 1]], &[], false, true)?;
-        let s = core::str::from_utf8(peek.slice)
-            .map_err(|e| Located::new(self.line, Error::Utf8Error(e)))?;
-        self.advance(peek);
+        let peek = self.peek_until_match(
+            &[&STRING_LITERAL_DELIM[..hash_count + 1]],
+            false,
+            Some(Error::UnterminatedLiteralString),
+        )?;
+        let s = self.peek_str(&peek)?;
+        self.advance(peek)?;
 
         // Advance past the end delimiter
         self.pos += hash_count + 1;
@@ -262,21 +415,78 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    // Numbers are only validated by the parser, so anything that doesn't look
+    // like the start of a date (`YYYY-`) is lexed as a number here.
     fn try_parse_number(&mut self) -> Result<Option<Token<'a>>, Located<Error>> {
-        todo!()
+        let peek = self.peek_word()?;
+        if is_date_like(peek.slice) {
+            return Ok(None);
+        }
+        let s = self.peek_str(&peek)?;
+        self.advance(peek)?;
+        Ok(Some(Token::Value(SimpleValue::Number(s))))
     }
 
     fn try_parse_datetime(&mut self) -> Result<Option<Token<'a>>, Located<Error>> {
-        todo!()
+        let peek = self.peek_word()?;
+        if !matches!(peek.slice.get(10), Some(b'T' | b't')) {
+            return Ok(None);
+        }
+        let s = self.peek_str(&peek)?;
+        self.advance(peek)?;
+        Ok(Some(Token::Value(SimpleValue::DateTime(s))))
     }
 
     fn parse_date(&mut self) -> Result<Token<'a>, Located<Error>> {
-        todo!()
+        let peek = self.peek_word()?;
+        let s = self.peek_str(&peek)?;
+        self.advance(peek)?;
+        Ok(Token::Value(SimpleValue::Date(s)))
+    }
+
+    // Peeks until whitespace, a delimiter, the start of a comment or the end
+    // of the input.
+    #[inline]
+    fn peek_word(&self) -> Result<Peek<'a>, Located<Error>> {
+        self.peek_until_match(WORD_TERMINATORS, false, None)
+    }
+
+    // Returns the peeked slice as a string. Since our input is already valid
+    // UTF-8, and peeks always fall on character boundaries, this only needs to
+    // slice into the original input rather than validating the bytes again.
+    // Slices not on character boundaries (which indicates a bug in the lexer)
+    // fall back to full validation to produce an appropriate error.
+    #[inline]
+    fn peek_str(&self, peek: &Peek<'a>) -> Result<&'a str, Located<Error>> {
+        match self.text.get(peek.from..peek.from + peek.slice.len()) {
+            Some(s) => Ok(s),
+            None => core::str::from_utf8(peek.slice)
+                .map_err(|e| Located::new(self.line, self.col(self.pos), Error::Utf8Error(e))),
+        }
     }
 
     #[inline]
     fn located_err<T, E>(&self, err: E) -> Result<T, Located<E>> {
-        located_err(self.line, err)
+        located_err(self.line, self.col(self.pos), err)
+    }
+
+    // Locates the given error at the start of the most recent token.
+    #[inline]
+    fn token_err<T, E>(&self, err: E) -> Result<T, Located<E>> {
+        located_err(self.token_line, self.col(self.token_pos), err)
+    }
+
+    // Computes the (1-based) column of the given position by counting the
+    // characters between it and the start of its line.
+    fn col(&self, pos: usize) -> usize {
+        let mut start = pos;
+        while start > 0 && !is_line_ending(self.src, start - 1) {
+            start -= 1;
+        }
+        if start == 0 && self.src.starts_with(UTF8_BOM) {
+            start = UTF8_BOM.len();
+        }
+        self.text.get(start..pos).map_or(0, |s| s.chars().count()) + 1
     }
 
     // Peeks up to `len` characters.
@@ -298,62 +508,102 @@ impl<'a> Lexer<'a> {
     }
 
     // Peeks until we match any of the given byte strings. Includes the matching
-    // slice at the end of the match. Skips over any matching byte strings in
-    // `skip`.
+    // slice at the end of the match. If `unterminated` is given, it is returned as an error if we hit
+    // the end of the input without finding a match.
+    //
+    // Rather than decoding each character, this scans the input bytes for the
+    // final byte of any of the byte strings in `opts`, and only then compares
+    // the preceding bytes. This is safe for UTF-8 input since a complete
+    // character can only match at the end of a character in the input.
     fn peek_until_match(
         &self,
         opts: &[&[u8]],
-        skip: &[&[u8]],
         include_match: bool,
-        must_match: bool,
+        unterminated: Option<Error>,
     ) -> Result<Peek<'a>, Located<Error>> {
-        let mut pos = self.pos;
-        let mut match_end = pos;
+        // Whether or not the byte string ends at (and includes) the byte at
+        // `end`, without starting before our current position
+        let ends_at =
+            |s: &[u8], end: usize| end + 1 >= self.pos + s.len() && self.src[..=end].ends_with(s);
         let mut lines = 0;
-        let mut buf = [0_u8; MATCH_BUF_SIZE];
-        let mut matched = false;
-        'outer: while pos < self.src.len() {
-            let peek = self.peek_char_at(pos)?;
-            pos += peek.slice.len();
-            match_end = pos;
-            lines += peek.lines;
-
-            // Rotate the buffer left by enough elements to inject the new slice
-            // at the end of the buffer
-            for i in 0..MATCH_BUF_SIZE - peek.slice.len() {
-                buf[i] = buf[i + peek.slice.len()];
+        for pos in self.pos..self.len {
+            let b = self.src[pos];
+            if is_line_ending(self.src, pos) {
+                lines += 1;
             }
-            // Inject the new slice at the end of the buffer
-            for (i, b) in peek.slice.iter().enumerate() {
-                buf[MATCH_BUF_SIZE - peek.slice.len() + i] = *b;
+            if !opts.iter().any(|opt| opt.last() == Some(&b)) {
+                continue;
             }
-            for sk in skip {
-                if &buf[MATCH_BUF_SIZE - sk.len()..] == *sk {
-                    continue 'outer;
+            if let Some(opt) = opts.iter().find(|opt| ends_at(opt, pos)) {
+                let mut match_end = pos + 1;
+                if !include_match {
+                    match_end -= opt.len();
+                    lines -= count_lines(self.src, match_end..pos + 1);
                 }
+                return Ok(Peek {
+                    slice: &self.src[self.pos..match_end],
+                    from: self.pos,
+                    lines,
+                });
             }
-            for opt in opts {
-                if &buf[MATCH_BUF_SIZE - opt.len()..] == *opt {
-                    if !include_match {
-                        match_end -= opt.len();
-                    }
-                    matched = true;
-                    break 'outer;
+        }
+        if let Some(e) = unterminated {
+            return self.token_err(e);
+        }
+        Ok(Peek {
+            slice: &self.src[self.pos..],
+            from: self.pos,
+            lines,
+        })
+    }
+
+    // Peeks until the given closing quote, excluding it. A backslash escapes
+    // the byte following it, so in `"a\\"` the second quote closes the string,
+    // while in `"a\"` it doesn't. Bytes following a backslash may be part of a
+    // multi-byte character, but can then never be mistaken for a quote or a
+    // backslash.
+    fn peek_until_quote(&self, quote: u8) -> Result<Peek<'a>, Located<Error>> {
+        let mut pos = self.pos;
+        while pos < self.len {
+            match self.src[pos] {
+                b'\\' => pos += 2,
+                b if b == quote => {
+                    return Ok(Peek {
+                        slice: &self.src[self.pos..pos],
+                        from: self.pos,
+                        lines: count_lines(self.src, self.pos..pos),
+                    })
                 }
+                _ => pos += 1,
             }
         }
-        if must_match && !matched {
-            return self.located_err(Error::MissingTerminator);
+        self.token_err(Error::UnterminatedString)
+    }
+
+    // Peeks up to and including the end of the current line (or the end of the
+    // input).
+    fn peek_line(&self) -> Result<Peek<'a>, Located<Error>> {
+        let mut pos = self.pos;
+        let mut lines = 0;
+        while pos < self.len && lines == 0 {
+            let peek = self.peek_char_at(pos)?;
+            pos += peek.slice.len();
+            lines += peek.lines;
         }
         Ok(Peek {
-            slice: &self.src[self.pos..match_end],
+            slice: &self.src[self.pos..pos],
             from: self.pos,
             lines,
         })
     }
 
-    // Peeks until we don't match any of the given byte strings.
-    fn peek_until_not_match(&self, opts: &[&[u8]]) -> Result<Peek<'a>, Located<Error>> {
+    // Peeks until we don't match any of the given byte strings. Returns the
+    // `unterminated` error if we hit the end of the input while still matching.
+    fn peek_until_not_match(
+        &self,
+        opts: &[&[u8]],
+        unterminated: Error,
+    ) -> Result<Peek<'a>, Located<Error>> {
         let mut pos = self.pos;
         let mut match_end = pos;
         let mut lines = 0;
@@ -385,7 +635,7 @@ impl<'a> Lexer<'a> {
             break;
         }
         if !matched {
-            return self.located_err(Error::MissingTerminator);
+            return self.token_err(unterminated);
         }
         Ok(Peek {
             slice: &self.src[self.pos..match_end],
@@ -425,12 +675,299 @@ impl<'a> Lexer<'a> {
             Ok(Peek {
                 slice: &self.src[pos..pos + ch_len],
                 from: pos,
-                lines: if ch_len == 1 && b == b'\n' { 1 } else { 0 },
+                lines: if is_line_ending(self.src, pos) { 1 } else { 0 },
             })
         }
     }
 }
 
+/// Options to control the behaviour of the [`Parser`].
+#[derive(Debug, Clone)]
+pub struct ParserOptions {
+    /// If set, allows the same property ID to be used more than once in a
+    /// single object, where the last value (and its docstring) wins. Otherwise
+    /// duplicate property IDs result in an [`Error::DuplicatePropertyId`].
+    pub allow_duplicate_ids: bool,
+    /// The maximum number of objects and arrays that may be nested within
+    /// each other. Deeper nesting results in an [`Error::MaxDepthExceeded`],
+    /// which prevents hostile input from overflowing the stack.
+    pub max_depth: usize,
+    /// Options for the underlying [`Lexer`], e.g. to allow single-quoted
+    /// strings.
+    pub lexer: LexerOptions,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            allow_duplicate_ids: false,
+            max_depth: DEFAULT_MAX_DEPTH,
+            lexer: LexerOptions::default(),
+        }
+    }
+}
+
+/// The default maximum nesting depth of objects and arrays.
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// Builds a [`Document`] from the tokens produced by a [`Lexer`].
+pub struct Parser<'a> {
+    lexer: Lexer<'a>,
+    opts: ParserOptions,
+    depth: usize, // The number of objects/arrays we are currently nested in
+    peeked: Option<Token<'a>>,
+}
+
+impl<'a> From<&'a str> for Parser<'a> {
+    fn from(s: &'a str) -> Self {
+        Self::new(s, ParserOptions::default())
+    }
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(s: &'a str, opts: ParserOptions) -> Self {
+        Self {
+            lexer: Lexer::new(s, opts.lexer.clone()),
+            opts,
+            depth: 0,
+            peeked: None,
+        }
+    }
+
+    /// Parses the entire input as a single document, consisting of an optional
+    /// docstring followed by exactly one value.
+    ///
+    /// An empty document (i.e. one containing only whitespace and/or comments)
+    /// parses to [`Value::Null`] with no docstring. A docstring that is not
+    /// followed by a value results in an [`Error::UnexpectedEof`].
+    pub fn parse(mut self) -> Result<Document<'a>, Located<Error>> {
+        let (docstring, token) = self.next_documented()?;
+        let token = match token {
+            Some(t) => t,
+            None if docstring.is_empty() => {
+                return Ok(Document {
+                    docstring,
+                    value: Value::Null,
+                })
+            }
+            None => return self.lexer.located_err(Error::UnexpectedEof),
+        };
+        let value = self.parse_value(token)?;
+        if self.next_token()?.is_some() {
+            return self.lexer.token_err(Error::UnexpectedToken);
+        }
+        Ok(Document { docstring, value })
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token<'a>>, Located<Error>> {
+        match self.peeked.take() {
+            Some(t) => Ok(Some(t)),
+            None => self.lexer.next().transpose(),
+        }
+    }
+
+    // Peeks at the next token without consuming it. While a token is peeked,
+    // the lexer's token position refers to that token.
+    fn peek_token(&mut self) -> Result<Option<&Token<'a>>, Located<Error>> {
+        if self.peeked.is_none() {
+            self.peeked = self.lexer.next().transpose()?;
+        }
+        Ok(self.peeked.as_ref())
+    }
+
+    // Consumes the next token if it's a comma, which optionally separates
+    // array elements and object properties.
+    fn skip_comma(&mut self) -> Result<(), Located<Error>> {
+        if let Some(Token::Comma) = self.peek_token()? {
+            self.peeked = None;
+        }
+        Ok(())
+    }
+
+    fn expect_token(&mut self) -> Result<Token<'a>, Located<Error>> {
+        match self.next_token()? {
+            Some(t) => Ok(t),
+            None => self.lexer.located_err(Error::UnexpectedEof),
+        }
+    }
+
+    // Collects any docstring lines preceding the next non-docstring token.
+    fn next_documented(
+        &mut self,
+    ) -> Result<(MultiLineString<'a>, Option<Token<'a>>), Located<Error>> {
+        let mut docstring = Vec::new();
+        loop {
+            match self.next_token()? {
+                Some(Token::DocstringLine(line)) => docstring.push(line),
+                t => return Ok((docstring, t)),
+            }
+        }
+    }
+
+    fn parse_value(&mut self, token: Token<'a>) -> Result<Value<'a>, Located<Error>> {
+        match token {
+            Token::ObjectStart => self.nested(Self::parse_object),
+            Token::ArrayStart => self.nested(Self::parse_array),
+            Token::Value(v) => self.parse_simple_value(v),
+            _ => self.lexer.token_err(Error::UnexpectedToken),
+        }
+    }
+
+    // Parses a nested object or array, enforcing the maximum nesting depth.
+    // Must be called immediately after lexing the opening token.
+    fn nested<F>(&mut self, parse: F) -> Result<Value<'a>, Located<Error>>
+    where
+        F: FnOnce(&mut Self) -> Result<Value<'a>, Located<Error>>,
+    {
+        if self.depth >= self.opts.max_depth {
+            return self.lexer.token_err(Error::MaxDepthExceeded {
+                depth: self.depth + 1,
+                limit: self.opts.max_depth,
+            });
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn parse_object(&mut self) -> Result<Value<'a>, Located<Error>> {
+        let mut props = DocumentedProperties::new();
+        // Maps the ID of each property in `props` to its index and the line on
+        // which it was defined
+        let mut ids = BTreeMap::new();
+        loop {
+            let (mut docstring, token) = self.next_documented()?;
+            let id = match token {
+                Some(Token::ObjectEnd) if docstring.is_empty() => return Ok(Value::Object(props)),
+                Some(Token::PropertyId(id)) => id,
+                Some(_) => return self.lexer.token_err(Error::UnexpectedToken),
+                None => return self.lexer.located_err(Error::UnexpectedEof),
+            };
+            // The column is only computed if needed, since doing so scans back
+            // to the start of the line
+            let (line, pos) = (self.lexer.token_line, self.lexer.token_pos);
+            let token = self.expect_token()?;
+            let value = self.parse_value(token)?;
+            let value_line = self.lexer.line;
+            self.skip_comma()?;
+            // A docstring line starting on the same line as the value documents
+            // this property
+            if let Some(Token::DocstringLine(doc_line)) = self.peek_token()? {
+                let doc_line = *doc_line;
+                if self.lexer.token_line == value_line {
+                    docstring.push(doc_line);
+                    self.peeked = None;
+                }
+            }
+            let prop = DocumentedProperty {
+                docstring,
+                id,
+                value,
+            };
+            match ids.get(id) {
+                Some(&(i, _)) if self.opts.allow_duplicate_ids => props[i] = prop,
+                Some(&(_, id_line)) => {
+                    return located_err(
+                        line,
+                        self.lexer.col(pos),
+                        Error::DuplicatePropertyId { id_line },
+                    )
+                }
+                None => {
+                    ids.insert(id, (props.len(), line));
+                    props.push(prop);
+                }
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Value<'a>, Located<Error>> {
+        let mut values = Vec::new();
+        loop {
+            match self.expect_token()? {
+                Token::ArrayEnd => return Ok(Value::Array(values)),
+                token => {
+                    values.push(self.parse_value(token)?);
+                    self.skip_comma()?;
+                }
+            }
+        }
+    }
+
+    fn parse_simple_value(&self, v: SimpleValue<'a>) -> Result<Value<'a>, Located<Error>> {
+        Ok(match v {
+            SimpleValue::Null => Value::Null,
+            SimpleValue::Bool(b) => Value::Bool(b),
+            SimpleValue::Number(s) => Value::Number(self.parse_str(s)?),
+            SimpleValue::Date(s) => Value::Date(self.parse_str(s)?),
+            SimpleValue::DateTime(s) => Value::DateTime(self.parse_str(s)?),
+            SimpleValue::String(s) => Value::String(ValueString::Regular(
+                MaybeLiteralString::NonLiteral(split_lines(self.check_escapes(s)?)),
+            )),
+            SimpleValue::DedentString(s) => Value::String(ValueString::Dedent(
+                MaybeLiteralString::NonLiteral(split_lines(self.check_escapes(s)?)),
+            )),
+            SimpleValue::LiteralString(s) => Value::String(ValueString::Regular(
+                MaybeLiteralString::Literal(split_lines(s)),
+            )),
+            SimpleValue::DedentLiteralString(s) => Value::String(ValueString::Dedent(
+                MaybeLiteralString::Literal(split_lines(s)),
+            )),
+        })
+    }
+
+    // Ensures that the escape sequences in a non-literal string can be
+    // decoded. Strings are kept as written, and only decoded when their text
+    // is requested (see `Value::as_str`).
+    fn check_escapes(&self, s: &'a str) -> Result<&'a str, Located<Error>> {
+        unescape_regular_string(s).map_err(|e| {
+            Located::new(
+                self.lexer.token_line,
+                self.lexer.col(self.lexer.token_pos),
+                e,
+            )
+        })?;
+        Ok(s)
+    }
+
+    fn parse_str<T>(&self, s: &str) -> Result<T, Located<Error>>
+    where
+        T: core::str::FromStr<Err = Error>,
+    {
+        s.parse().map_err(|e| {
+            Located::new(
+                self.lexer.token_line,
+                self.lexer.col(self.lexer.token_pos),
+                e,
+            )
+        })
+    }
+}
+
+// Splits a string's contents into lines, excluding their line endings. Line
+// endings are recognized in the same way as the lexer counts lines (see
+// `is_line_ending`), so "\r\n" and a lone "\r" are both equivalent to "\n".
+// All other whitespace is preserved verbatim.
+fn split_lines(s: &str) -> MultiLineString<'_> {
+    let bytes = s.as_bytes();
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for i in 0..bytes.len() {
+        if is_line_ending(bytes, i) {
+            let end = if bytes[i] == b'\n' && i > start && bytes[i - 1] == b'\r' {
+                i - 1
+            } else {
+                i
+            };
+            lines.push(&s[start..end]);
+            start = i + 1;
+        }
+    }
+    lines.push(&s[start..]);
+    lines
+}
+
 struct Peek<'a> {
     slice: &'a [u8],
     from: usize,
@@ -446,20 +983,113 @@ impl<'a> Peek<'a> {
     }
 }
 
+// Returns whether the byte at `pos` ends a line. A "\n" always ends a line, as
+// does a lone "\r" (classic Mac line endings). The "\r" in a "\r\n" pair does
+// not, so that the pair only counts as a single line ending.
 #[inline]
-fn is_valid_property_id(s: &[u8]) -> bool {
-    if s.is_empty() {
-        return false;
+pub(crate) fn is_line_ending(src: &[u8], pos: usize) -> bool {
+    match src[pos] {
+        b'\n' => true,
+        b'\r' => src.get(pos + 1) != Some(&b'\n'),
+        _ => false,
     }
-    if !matches!(s[0], b'a'..=b'z' | b'A'..=b'Z' | b'_') {
-        return false;
+}
+
+/// Decodes the escape sequences in the contents of a regular (non-literal)
+/// string. The supported escape sequences are `\\`, `\"`, `\'`, `\n`, `\t`,
+/// `\r`, `\xHH` (with exactly 2 hexadecimal digits, for characters up to
+/// U+00FF) and `\u{X}` (with 1-6 hexadecimal digits). This is the inverse of
+/// [`crate::escape_regular_string`].
+///
+/// A backslash at the end of a line is a line continuation: the backslash,
+/// the line ending and any spaces or tabs at the start of the following line
+/// are removed, joining the two lines.
+///
+/// Any other backslash results in an [`Error::InvalidEscape`]. Strings without
+/// any backslashes are returned as is, without allocating.
+pub fn unescape_regular_string(s: &str) -> Result<Cow<'_, str>, Error> {
+    if !s.contains('\\') {
+        return Ok(Cow::Borrowed(s));
     }
-    for b in &s[1..] {
-        if !matches!(b, b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_') {
-            return false;
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next().ok_or(Error::InvalidEscape)? {
+            '\\' => unescaped.push('\\'),
+            '"' => unescaped.push('"'),
+            '\'' => unescaped.push('\''),
+            'n' => unescaped.push('\n'),
+            't' => unescaped.push('\t'),
+            'r' => unescaped.push('\r'),
+            'x' => {
+                let mut code = 0_u8;
+                for _ in 0..2 {
+                    let digit = chars.next().and_then(|c| c.to_digit(16));
+                    code = code * 16 + digit.ok_or(Error::InvalidEscape)? as u8;
+                }
+                unescaped.push(char::from(code));
+            }
+            'u' => {
+                if chars.next() != Some('{') {
+                    return Err(Error::InvalidEscape);
+                }
+                let mut code = 0_u32;
+                let mut digits = 0;
+                loop {
+                    match chars.next().ok_or(Error::InvalidEscape)? {
+                        '}' if digits > 0 => break,
+                        c if digits < 6 => {
+                            code = code * 16 + c.to_digit(16).ok_or(Error::InvalidEscape)?;
+                            digits += 1;
+                        }
+                        _ => return Err(Error::InvalidEscape),
+                    }
+                }
+                unescaped.push(char::from_u32(code).ok_or(Error::InvalidEscape)?);
+            }
+            c @ ('\n' | '\r') => {
+                // Treat "\r\n" as a single line ending
+                if c == '\r' && chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                while matches!(chars.peek(), Some(' ' | '\t')) {
+                    chars.next();
+                }
+            }
+            _ => return Err(Error::InvalidEscape),
         }
     }
-    true
+    Ok(Cow::Owned(unescaped))
+}
+
+// Counts the line endings within the given range of `src`.
+#[inline]
+fn count_lines(src: &[u8], range: Range<usize>) -> usize {
+    range.filter(|&pos| is_line_ending(src, pos)).count()
+}
+
+// Returns whether the given word starts like a date (i.e. `YYYY-`).
+#[inline]
+fn is_date_like(s: &[u8]) -> bool {
+    s.len() > 4 && s[..4].iter().all(u8::is_ascii_digit) && s[4] == b'-'
+}
+
+// Returns the byte position of the first character that is not allowed in the
+// given property ID, or `None` if the property ID is valid. An empty property
+// ID is invalid at position 0.
+#[inline]
+fn invalid_property_id_pos(s: &[u8]) -> Option<usize> {
+    if s.is_empty() || !matches!(s[0], b'a'..=b'z' | b'A'..=b'Z' | b'_') {
+        return Some(0);
+    }
+    s[1..]
+        .iter()
+        .position(|b| !matches!(b, b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_'))
+        .map(|i| i + 1)
 }
 
 // Fast lookup table taken from core::str::validation
@@ -485,10 +1115,12 @@ const UTF8_CHAR_WIDTH: &[u8; 256] = &[
 ];
 
 #[cfg(test)]
+#[allow(clippy::explicit_auto_deref)]
 mod test {
-    use alloc::vec::Vec;
+    use alloc::{format, vec};
 
     use super::*;
+    use crate::Number;
 
     #[test]
     fn peek_to_len() {
@@ -498,7 +1130,7 @@ mod test {
         assert_eq!(peek.from, 0);
         assert_eq!(peek.lines, 0);
 
-        lexer.advance(peek);
+        lexer.advance(peek).unwrap();
         let peek = lexer.peek_to_len(4).unwrap();
         assert_eq!(peek.slice, b" str");
         assert_eq!(peek.from, 6);
@@ -514,7 +1146,7 @@ mod test {
         for (tc, opt, expected) in TEST_CASES {
             let lexer = Lexer::from(*tc);
             let peek = lexer
-                .peek_until_match(&[opt.as_bytes()], &[], true, true)
+                .peek_until_match(&[opt.as_bytes()], true, Some(Error::MissingTerminator))
                 .unwrap();
             assert_eq!(peek.slice, expected.as_bytes());
         }
@@ -557,10 +1189,10 @@ mod test {
 
     #[test]
     fn unexpected_char() {
-        const TEST_CASES: &[&str] = &["😂", "$", "   $"];
-        for tc in TEST_CASES {
+        const TEST_CASES: &[(&str, usize)] = &[("😂", 1), ("$", 1), ("   $", 4), ("\t😂", 2)];
+        for (tc, col) in TEST_CASES {
             let r = Lexer::from(*tc).next().unwrap();
-            assert_eq!(r, located_err(1, Error::UnexpectedChar));
+            assert_eq!(r, located_err(1, *col, Error::UnexpectedChar));
         }
     }
 
@@ -682,4 +1314,1204 @@ mod test {
             assert_eq!(Vec::from(*expected), actual, "test case {}", i);
         }
     }
+
+    #[test]
+    fn bom_lexing() {
+        const TEST_CASES: &[&str] = &[
+            "/// Docstring\nsome-bool true\nlit #\"literal\"#",
+            "empty null",
+            "",
+        ];
+        for tc in TEST_CASES {
+            let expected = Lexer::from(*tc)
+                .collect::<Result<Vec<Token>, Located<Error>>>()
+                .unwrap();
+            let with_bom = format!("\u{FEFF}{}", tc);
+            let actual = Lexer::from(with_bom.as_str())
+                .collect::<Result<Vec<Token>, Located<Error>>>()
+                .unwrap();
+            assert_eq!(expected, actual, "test case: {}", tc);
+        }
+    }
+
+    #[test]
+    fn bom_not_at_start() {
+        const TEST_CASES: &[&str] = &[" \u{FEFF}", "null\n\u{FEFF}", "\u{FEFF}\u{FEFF}"];
+        for tc in TEST_CASES {
+            let r = Lexer::from(*tc)
+                .collect::<Result<Vec<Token>, Located<Error>>>()
+                .unwrap_err();
+            assert_eq!(r.err, Error::UnexpectedChar, "test case: {:?}", tc);
+        }
+    }
+
+    #[test]
+    fn bracket_lexing() {
+        let actual = Lexer::from("{ a [ true ] b {} }")
+            .collect::<Result<Vec<Token>, Located<Error>>>()
+            .unwrap();
+        assert_eq!(
+            actual,
+            vec![
+                Token::ObjectStart,
+                Token::PropertyId("a"),
+                Token::ArrayStart,
+                Token::Value(SimpleValue::Bool(true)),
+                Token::ArrayEnd,
+                Token::PropertyId("b"),
+                Token::ObjectStart,
+                Token::ObjectEnd,
+                Token::ObjectEnd,
+            ]
+        );
+    }
+
+    fn regular_string(lines: Vec<&str>) -> Value<'_> {
+        Value::String(ValueString::Regular(MaybeLiteralString::NonLiteral(lines)))
+    }
+
+    #[test]
+    fn document_parsing() {
+        let doc = Parser::from(
+            r##"
+/// A document.
+{
+    /// The title.
+    title "Hello\nworld"
+    tags [ "a" #"b"# null ]
+    nested { draft false }
+}
+"##,
+        )
+        .parse()
+        .unwrap();
+        assert_eq!(doc.docstring, vec![" A document.\n"]);
+        assert_eq!(
+            doc.value,
+            Value::Object(vec![
+                DocumentedProperty {
+                    docstring: vec![" The title.\n"],
+                    id: "title",
+                    value: regular_string(vec!["Hello\\nworld"]),
+                },
+                DocumentedProperty {
+                    docstring: vec![],
+                    id: "tags",
+                    value: Value::Array(vec![
+                        regular_string(vec!["a"]),
+                        Value::String(ValueString::Regular(MaybeLiteralString::Literal(vec!["b"]))),
+                        Value::Null,
+                    ]),
+                },
+                DocumentedProperty {
+                    docstring: vec![],
+                    id: "nested",
+                    value: Value::Object(vec![DocumentedProperty {
+                        docstring: vec![],
+                        id: "draft",
+                        value: Value::Bool(false),
+                    }]),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn multi_line_string_parsing() {
+        let doc = Parser::from("\"first\nsecond\"").parse().unwrap();
+        assert_eq!(doc.value, regular_string(vec!["first", "second"]));
+
+        // Interior whitespace is preserved verbatim, while all line endings
+        // are equivalent
+        let doc = Parser::from("\"  first \r\n\tsecond\r\rthird\n\n  \"")
+            .parse()
+            .unwrap();
+        assert_eq!(
+            doc.value,
+            regular_string(vec!["  first ", "\tsecond", "", "third", "", "  "])
+        );
+        assert_eq!(
+            doc.value.as_str().unwrap(),
+            "  first \n\tsecond\n\nthird\n\n  "
+        );
+    }
+
+    #[test]
+    fn multi_line_dedent_string_parsing() {
+        let doc = Parser::from("d\"\r\n    first\r\n      second\r\n\r\n    third\r\n  \"")
+            .parse()
+            .unwrap();
+        // The lines themselves are kept as written, with the dedent rule only
+        // applied when retrieving the text of the string
+        assert_eq!(
+            doc.value,
+            Value::String(ValueString::Dedent(MaybeLiteralString::NonLiteral(vec![
+                "",
+                "    first",
+                "      second",
+                "",
+                "    third",
+                "  ",
+            ])))
+        );
+        assert_eq!(doc.value.as_str().unwrap(), "first\n  second\n\nthird");
+    }
+
+    #[test]
+    fn invalid_documents() {
+        const TEST_CASES: &[(&str, Located<Error>)] = &[
+            (
+                "{ a true",
+                Located {
+                    line: 1,
+                    col: 9,
+                    err: Error::UnexpectedEof,
+                },
+            ),
+            (
+                "[ true",
+                Located {
+                    line: 1,
+                    col: 7,
+                    err: Error::UnexpectedEof,
+                },
+            ),
+            (
+                "true false",
+                Located {
+                    line: 1,
+                    col: 6,
+                    err: Error::UnexpectedToken,
+                },
+            ),
+            (
+                "{ true }",
+                Located {
+                    line: 1,
+                    col: 3,
+                    err: Error::UnexpectedToken,
+                },
+            ),
+            (
+                "{ a b }",
+                Located {
+                    line: 1,
+                    col: 5,
+                    err: Error::UnexpectedToken,
+                },
+            ),
+            (
+                "{\n/// Dangling\n}",
+                Located {
+                    line: 3,
+                    col: 1,
+                    err: Error::UnexpectedToken,
+                },
+            ),
+            (
+                "[,]",
+                Located {
+                    line: 1,
+                    col: 2,
+                    err: Error::UnexpectedToken,
+                },
+            ),
+            (
+                "[1,,2]",
+                Located {
+                    line: 1,
+                    col: 4,
+                    err: Error::UnexpectedToken,
+                },
+            ),
+            (
+                "{ a 1,, b 2 }",
+                Located {
+                    line: 1,
+                    col: 7,
+                    err: Error::UnexpectedToken,
+                },
+            ),
+            (
+                "1,",
+                Located {
+                    line: 1,
+                    col: 2,
+                    err: Error::UnexpectedToken,
+                },
+            ),
+        ];
+        for (tc, expected) in TEST_CASES {
+            let actual = Parser::from(*tc).parse().unwrap_err();
+            assert_eq!(actual, *expected, "test case: {}", tc);
+        }
+    }
+
+    #[test]
+    fn empty_documents() {
+        const TEST_CASES: &[&str] = &[
+            "",
+            "   \n\t\r\n",
+            "// Just a comment",
+            "/* A block comment */\n// And a line comment\n",
+            "\u{FEFF}",
+        ];
+        for tc in TEST_CASES {
+            let doc = Parser::from(*tc).parse().expect(tc);
+            assert_eq!(
+                doc,
+                Document {
+                    docstring: vec![],
+                    value: Value::Null,
+                },
+                "test case: {:?}",
+                tc
+            );
+        }
+
+        let r = Parser::from("/// A docstring without a value\n")
+            .parse()
+            .unwrap_err();
+        assert_eq!(r, Located::new(2, 1, Error::UnexpectedEof));
+        let r = Parser::from("}").parse().unwrap_err();
+        assert_eq!(r, Located::new(1, 1, Error::UnexpectedToken));
+        let r = Parser::from("// Comment\n  }").parse().unwrap_err();
+        assert_eq!(r, Located::new(2, 3, Error::UnexpectedToken));
+        let r = Parser::from("]").parse().unwrap_err();
+        assert_eq!(r, Located::new(1, 1, Error::UnexpectedToken));
+    }
+
+    const DUPLICATE_IDS: &str = r#"{
+    /// First title.
+    title "first"
+    draft true
+    /// Second title.
+    title "second"
+}"#;
+
+    #[test]
+    fn max_nesting_depth() {
+        let nested = |depth: usize| "[".repeat(depth) + &"]".repeat(depth);
+        let src = nested(DEFAULT_MAX_DEPTH);
+        assert!(Parser::from(src.as_str()).parse().is_ok());
+
+        let src = nested(DEFAULT_MAX_DEPTH + 1);
+        let r = Parser::from(src.as_str()).parse().unwrap_err();
+        assert_eq!(
+            r,
+            Located::new(
+                1,
+                DEFAULT_MAX_DEPTH + 1,
+                Error::MaxDepthExceeded {
+                    depth: DEFAULT_MAX_DEPTH + 1,
+                    limit: DEFAULT_MAX_DEPTH,
+                }
+            )
+        );
+
+        // Objects and arrays both count towards the depth
+        let opts = ParserOptions {
+            max_depth: 2,
+            ..Default::default()
+        };
+        assert!(Parser::new("{ a [] }", opts.clone()).parse().is_ok());
+        let r = Parser::new("{ a [ { } ] }", opts).parse().unwrap_err();
+        assert_eq!(
+            r,
+            Located::new(1, 7, Error::MaxDepthExceeded { depth: 3, limit: 2 })
+        );
+    }
+
+    #[test]
+    fn duplicate_property_ids() {
+        let r = Parser::from(DUPLICATE_IDS).parse().unwrap_err();
+        assert_eq!(
+            r,
+            Located::new(6, 5, Error::DuplicatePropertyId { id_line: 3 })
+        );
+    }
+
+    #[test]
+    fn lenient_duplicate_property_ids() {
+        let doc = Parser::new(
+            DUPLICATE_IDS,
+            ParserOptions {
+                allow_duplicate_ids: true,
+                ..Default::default()
+            },
+        )
+        .parse()
+        .unwrap();
+        assert_eq!(doc.value.len(), Some(2));
+        let title = doc.value.get_documented("title").unwrap();
+        assert_eq!(title.docstring, vec![" Second title.\n"]);
+        assert_eq!(title.value, regular_string(vec!["second"]));
+        assert_eq!(doc.value.get("draft"), Some(&Value::Bool(true)));
+    }
+
+    #[test]
+    fn unterminated_constructs() {
+        const TEST_CASES: &[(&str, Located<Error>)] = &[
+            (
+                "\"unclosed",
+                Located {
+                    line: 1,
+                    col: 1,
+                    err: Error::UnterminatedString,
+                },
+            ),
+            (
+                "{\n  a \"unclosed\nstring\n",
+                Located {
+                    line: 2,
+                    col: 5,
+                    err: Error::UnterminatedString,
+                },
+            ),
+            (
+                "d\"unclosed",
+                Located {
+                    line: 1,
+                    col: 1,
+                    err: Error::UnterminatedString,
+                },
+            ),
+            (
+                "#\"unclosed\"",
+                Located {
+                    line: 1,
+                    col: 1,
+                    err: Error::UnterminatedLiteralString,
+                },
+            ),
+            (
+                "\n##\"unclosed\"#",
+                Located {
+                    line: 2,
+                    col: 1,
+                    err: Error::UnterminatedLiteralString,
+                },
+            ),
+            (
+                "###",
+                Located {
+                    line: 1,
+                    col: 1,
+                    err: Error::UnterminatedLiteralString,
+                },
+            ),
+            (
+                "\n\n/* unclosed\n*",
+                Located {
+                    line: 3,
+                    col: 1,
+                    err: Error::UnterminatedBlockComment,
+                },
+            ),
+        ];
+        for (tc, expected) in TEST_CASES {
+            let actual = Lexer::from(*tc)
+                .collect::<Result<Vec<Token>, Located<Error>>>()
+                .unwrap_err();
+            assert_eq!(actual, *expected, "test case: {:?}", tc);
+        }
+    }
+
+    #[test]
+    fn line_endings() {
+        const DOC: &str =
+            "/// Docstring\n{\n  a true\n  /* A\n  comment */\n  b \"multi\nline\"\n}\n";
+        for ending in ["\n", "\r\n", "\r"] {
+            let tc = DOC.replace('\n', ending);
+            let mut lexer = Lexer::from(tc.as_str());
+            let tokens = lexer
+                .by_ref()
+                .collect::<Result<Vec<Token>, Located<Error>>>()
+                .unwrap();
+            assert_eq!(tokens.len(), 7, "line ending: {:?}", ending);
+            assert_eq!(lexer.line, 9, "line ending: {:?}", ending);
+
+            // Errors must be reported on the same line regardless of line
+            // endings
+            let tc = format!("{}$", tc);
+            let err = Lexer::from(tc.as_str())
+                .collect::<Result<Vec<Token>, Located<Error>>>()
+                .unwrap_err();
+            assert_eq!(err, Located::new(9, 1, Error::UnexpectedChar));
+        }
+    }
+
+    #[test]
+    fn peek_str() {
+        let lexer = Lexer::from("\"héllo wörld 😂\" ünicode");
+        let peek = lexer.peek_until_match(&[b" "], false, None).unwrap();
+        let s = lexer.peek_str(&peek).unwrap();
+        assert_eq!(s, "\"héllo");
+        // The string must be borrowed directly from the input
+        assert_eq!(s.as_ptr(), lexer.text.as_ptr());
+
+        // A slice that doesn't fall on a character boundary must be rejected
+        let peek = Peek {
+            slice: &lexer.src[0..3],
+            from: 0,
+            lines: 0,
+        };
+        assert!(matches!(
+            lexer.peek_str(&peek),
+            Err(Located {
+                err: Error::Utf8Error(_),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn multi_byte_string_lexing() {
+        const TEST_CASES: &[(&str, &[Token])] = &[
+            (
+                "/// Dökstring 😂\nid \"wörld 😂\"",
+                &[
+                    Token::DocstringLine(" Dökstring 😂\n"),
+                    Token::PropertyId("id"),
+                    Token::Value(SimpleValue::String("wörld 😂")),
+                ],
+            ),
+            (
+                "d##\"ünicode \"# literal\"##",
+                &[Token::Value(SimpleValue::DedentLiteralString(
+                    "ünicode \"# literal",
+                ))],
+            ),
+        ];
+        for (i, (tc, expected)) in TEST_CASES.iter().enumerate() {
+            let actual = Lexer::from(*tc)
+                .collect::<Result<Vec<Token>, Located<Error>>>()
+                .expect(tc);
+            assert_eq!(Vec::from(*expected), actual, "test case {}", i);
+        }
+    }
+
+    #[test]
+    fn property_id_termination() {
+        const TEST_CASES: &[(&str, &[Token])] = &[
+            ("just_an_id", &[Token::PropertyId("just_an_id")]),
+            ("x", &[Token::PropertyId("x")]),
+            ("d", &[Token::PropertyId("d")]),
+            ("nul", &[Token::PropertyId("nul")]),
+            ("nullable", &[Token::PropertyId("nullable")]),
+            ("trueish", &[Token::PropertyId("trueish")]),
+            ("falsey", &[Token::PropertyId("falsey")]),
+            ("false_flag", &[Token::PropertyId("false_flag")]),
+            (
+                "{ nullable true }",
+                &[
+                    Token::ObjectStart,
+                    Token::PropertyId("nullable"),
+                    Token::Value(SimpleValue::Bool(true)),
+                    Token::ObjectEnd,
+                ],
+            ),
+            (
+                "[null,true]",
+                &[
+                    Token::ArrayStart,
+                    Token::Value(SimpleValue::Null),
+                    Token::Comma,
+                    Token::Value(SimpleValue::Bool(true)),
+                    Token::ArrayEnd,
+                ],
+            ),
+            ("false// comment", &[Token::Value(SimpleValue::Bool(false))]),
+            (
+                "{ foo}",
+                &[
+                    Token::ObjectStart,
+                    Token::PropertyId("foo"),
+                    Token::ObjectEnd,
+                ],
+            ),
+            (
+                "{foo{}}",
+                &[
+                    Token::ObjectStart,
+                    Token::PropertyId("foo"),
+                    Token::ObjectStart,
+                    Token::ObjectEnd,
+                    Token::ObjectEnd,
+                ],
+            ),
+            (
+                "{ a[] }",
+                &[
+                    Token::ObjectStart,
+                    Token::PropertyId("a"),
+                    Token::ArrayStart,
+                    Token::ArrayEnd,
+                    Token::ObjectEnd,
+                ],
+            ),
+        ];
+        for (i, (tc, expected)) in TEST_CASES.iter().enumerate() {
+            let actual = Lexer::from(*tc)
+                .collect::<Result<Vec<Token>, Located<Error>>>()
+                .expect(tc);
+            assert_eq!(Vec::from(*expected), actual, "test case {}", i);
+        }
+    }
+
+    #[test]
+    fn number_and_date_lexing() {
+        const TEST_CASES: &[(&str, SimpleValue)] = &[
+            ("42", SimpleValue::Number("42")),
+            ("-1.5e3", SimpleValue::Number("-1.5e3")),
+            ("  0.25\n", SimpleValue::Number("0.25")),
+            ("2022", SimpleValue::Number("2022")),
+            ("2022-01-02", SimpleValue::Date("2022-01-02")),
+            (
+                "2022-01-02T03:04:05Z",
+                SimpleValue::DateTime("2022-01-02T03:04:05Z"),
+            ),
+            (
+                "2022-01-02t03:04:05.678+02:00",
+                SimpleValue::DateTime("2022-01-02t03:04:05.678+02:00"),
+            ),
+        ];
+        for (tc, expected) in TEST_CASES {
+            let t = Lexer::from(*tc).next().unwrap().unwrap();
+            assert_eq!(t, Token::Value(expected.clone()), "test case: {}", tc);
+        }
+    }
+
+    #[test]
+    fn trailing_line_comments() {
+        const TEST_CASES: &[(&str, &[Token])] = &[
+            (
+                "answer 42 // the answer",
+                &[
+                    Token::PropertyId("answer"),
+                    Token::Value(SimpleValue::Number("42")),
+                ],
+            ),
+            (
+                "answer 42// the answer",
+                &[
+                    Token::PropertyId("answer"),
+                    Token::Value(SimpleValue::Number("42")),
+                ],
+            ),
+            (
+                "when 2022-01-02/* a date */",
+                &[
+                    Token::PropertyId("when"),
+                    Token::Value(SimpleValue::Date("2022-01-02")),
+                ],
+            ),
+            (
+                "at 2022-01-02T03:04:05Z// a date/time",
+                &[
+                    Token::PropertyId("at"),
+                    Token::Value(SimpleValue::DateTime("2022-01-02T03:04:05Z")),
+                ],
+            ),
+            ("answer// no value", &[Token::PropertyId("answer")]),
+            ("answer // no value", &[Token::PropertyId("answer")]),
+            (
+                "name \"Gunny\" // a string",
+                &[
+                    Token::PropertyId("name"),
+                    Token::Value(SimpleValue::String("Gunny")),
+                ],
+            ),
+            (
+                "name \"Gunny\"// a string",
+                &[
+                    Token::PropertyId("name"),
+                    Token::Value(SimpleValue::String("Gunny")),
+                ],
+            ),
+        ];
+        for (i, (tc, expected)) in TEST_CASES.iter().enumerate() {
+            let actual = Lexer::from(*tc)
+                .collect::<Result<Vec<Token>, Located<Error>>>()
+                .expect(tc);
+            assert_eq!(Vec::from(*expected), actual, "test case {}", i);
+        }
+
+        // A lone slash is not a comment, so remains part of the number
+        let t = Lexer::from("1/2").next().unwrap().unwrap();
+        assert_eq!(t, Token::Value(SimpleValue::Number("1/2")));
+    }
+
+    #[test]
+    fn word_terminated_by_newline() {
+        // The terminating newline must only be counted once
+        let r = Lexer::from("{ a\n $ }")
+            .collect::<Result<Vec<Token>, Located<Error>>>()
+            .unwrap_err();
+        assert_eq!(r, Located::new(2, 2, Error::UnexpectedChar));
+        let r = Lexer::from("[ 1\n $ ]")
+            .collect::<Result<Vec<Token>, Located<Error>>>()
+            .unwrap_err();
+        assert_eq!(r, Located::new(2, 2, Error::UnexpectedChar));
+    }
+
+    #[test]
+    fn number_and_date_parsing() {
+        let doc = Parser::from(
+            r#"{
+    count 42 // a comment
+    offset -3
+    ratio 0.5
+    day 2022-01-02
+    at 2022-01-02T03:04:05Z
+}"#,
+        )
+        .parse()
+        .unwrap();
+        let value = doc.value;
+        assert_eq!(
+            value.get("count"),
+            Some(&Value::Number(Number::Unsigned(42)))
+        );
+        assert_eq!(
+            value.get("offset"),
+            Some(&Value::Number(Number::Signed(-3)))
+        );
+        assert_eq!(value.get("ratio"), Some(&Value::Number(Number::Float(0.5))));
+        assert_eq!(
+            value.get("day"),
+            Some(&Value::Date("2022-01-02".parse().unwrap()))
+        );
+        assert_eq!(
+            value.get("at"),
+            Some(&Value::DateTime("2022-01-02T03:04:05Z".parse().unwrap()))
+        );
+
+        let r = Parser::from("[ 1 2x ]").parse().unwrap_err();
+        assert_eq!(r, Located::new(1, 5, Error::InvalidNumber));
+        let r = Parser::from("2022-02-30").parse().unwrap_err();
+        assert_eq!(r, Located::new(1, 1, Error::InvalidDate));
+    }
+
+    #[test]
+    fn directive_lexing() {
+        let opts = LexerOptions {
+            allow_directives: true,
+            ..Default::default()
+        };
+        const TEST_CASES: &[(&str, Token)] = &[
+            ("@version 2", Token::Directive("version")),
+            ("  @schema-id \"post\"", Token::Directive("schema-id")),
+            ("version 2", Token::PropertyId("version")),
+        ];
+        for (tc, expected) in TEST_CASES {
+            let t = Lexer::new(tc, opts.clone()).next().unwrap().unwrap();
+            assert_eq!(t, *expected, "test case: {}", tc);
+        }
+
+        let r = Lexer::new("@ version", opts.clone()).next().unwrap();
+        assert_eq!(
+            r,
+            located_err(1, 2, Error::InvalidPropertyId { ch: None, col: 1 })
+        );
+        let r = Lexer::new("@1version", opts).next().unwrap();
+        assert_eq!(
+            r,
+            located_err(
+                1,
+                2,
+                Error::InvalidPropertyId {
+                    ch: Some('1'),
+                    col: 1
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn invalid_property_id_reports_char() {
+        let r = Lexer::from("{ na$me null }").nth(1).unwrap();
+        assert_eq!(
+            r,
+            located_err(
+                1,
+                3,
+                Error::InvalidPropertyId {
+                    ch: Some('$'),
+                    col: 3
+                }
+            )
+        );
+        let r = Lexer::from("naé").next().unwrap();
+        assert_eq!(
+            r,
+            located_err(
+                1,
+                1,
+                Error::InvalidPropertyId {
+                    ch: Some('é'),
+                    col: 3
+                }
+            )
+        );
+
+        // Digit-leading input is lexed as a number rather than a property ID,
+        // so check the validation itself
+        assert_eq!(invalid_property_id_pos(b"1name"), Some(0));
+        assert_eq!(invalid_property_id_pos(b"na$me"), Some(2));
+        assert_eq!(invalid_property_id_pos(b""), Some(0));
+        assert_eq!(invalid_property_id_pos(b"_na-me2"), None);
+    }
+
+    #[test]
+    fn directives_disabled_by_default() {
+        let r = Lexer::from("@version 2").next().unwrap();
+        assert_eq!(r, located_err(1, 1, Error::UnexpectedChar));
+    }
+
+    #[test]
+    fn spanned_lexing() {
+        let src = "/// Döc\n{ id \"😂 string\" lit ##\"a\"## /* skipped */ d\"x\" }";
+        let actual = Lexer::from(src)
+            .spanned()
+            .collect::<Result<Vec<_>, Located<Error>>>()
+            .unwrap();
+        let expected = vec![
+            (Token::DocstringLine(" Döc\n"), "/// Döc\n"),
+            (Token::ObjectStart, "{"),
+            (Token::PropertyId("id"), "id"),
+            (
+                Token::Value(SimpleValue::String("😂 string")),
+                "\"😂 string\"",
+            ),
+            (Token::PropertyId("lit"), "lit"),
+            (Token::Value(SimpleValue::LiteralString("a")), "##\"a\"##"),
+            (Token::Value(SimpleValue::DedentString("x")), "d\"x\""),
+            (Token::ObjectEnd, "}"),
+        ];
+        assert_eq!(actual.len(), expected.len());
+        for (spanned, (token, text)) in actual.iter().zip(expected) {
+            assert_eq!(spanned.inner, token);
+            assert_eq!(&src[spanned.span.clone()], text);
+        }
+        assert_eq!(actual[2].span, 11..13);
+        assert_eq!(actual[3].span, 14..27);
+    }
+
+    #[test]
+    fn single_quoted_string_lexing() {
+        let opts = LexerOptions {
+            single_quoted_strings: true,
+            ..Default::default()
+        };
+        const TEST_CASES: &[(&str, &[Token])] = &[
+            ("'hello'", &[Token::Value(SimpleValue::String("hello"))]),
+            (
+                "greeting 'it\\'s \"quoted\"'",
+                &[
+                    Token::PropertyId("greeting"),
+                    Token::Value(SimpleValue::String("it\\'s \"quoted\"")),
+                ],
+            ),
+            (
+                "\"it's\" #\"'literal'\"#",
+                &[
+                    Token::Value(SimpleValue::String("it's")),
+                    Token::Value(SimpleValue::LiteralString("'literal'")),
+                ],
+            ),
+        ];
+        for (i, (tc, expected)) in TEST_CASES.iter().enumerate() {
+            let actual = Lexer::new(tc, opts.clone())
+                .collect::<Result<Vec<Token>, Located<Error>>>()
+                .expect(tc);
+            assert_eq!(Vec::from(*expected), actual, "test case {}", i);
+        }
+
+        let r = Lexer::new("'unclosed", opts).next().unwrap();
+        assert_eq!(r, located_err(1, 1, Error::UnterminatedString));
+    }
+
+    #[test]
+    fn single_quoted_strings_disabled_by_default() {
+        let r = Lexer::from("'hello'").next().unwrap();
+        assert_eq!(r, located_err(1, 1, Error::UnexpectedChar));
+    }
+
+    #[test]
+    fn parser_lexer_options() {
+        const SRC: &str = "{ a 'it\\'s' }";
+        let r = Parser::from(SRC).parse().unwrap_err();
+        assert_eq!(r, Located::new(1, 5, Error::UnexpectedChar));
+
+        let opts = ParserOptions {
+            lexer: LexerOptions {
+                single_quoted_strings: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let doc = Parser::new(SRC, opts).parse().unwrap();
+        assert_eq!(doc.value.get("a"), Some(&regular_string(vec!["it\\'s"])));
+
+        let opts = ParserOptions {
+            lexer: LexerOptions {
+                allow_directives: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let r = Parser::new("{ @version 2 }", opts).parse().unwrap_err();
+        assert_eq!(r, Located::new(1, 3, Error::UnexpectedToken));
+    }
+
+    #[test]
+    fn literal_string_delimiters() {
+        const TEST_CASES: &[(&str, &str)] = &[
+            ("#\"\"#", ""),
+            ("#\"a#\"#", "a#"),
+            ("#\"a##\"#", "a##"),
+            ("#\"\"\"#", "\""),
+            ("##\"#\"##", "#"),
+            ("##\"a \"# b\"##", "a \"# b"),
+            ("##\"a\"#\"##", "a\"#"),
+            ("###\"a \"## b\"###", "a \"## b"),
+            ("###\"\"#\"##\"###", "\"#\"##"),
+            ("###\"trailing ##\"###", "trailing ##"),
+            (
+                "####################\"a \"################### b\"####################",
+                "a \"################### b",
+            ),
+        ];
+        for (tc, expected) in TEST_CASES {
+            let actual = Lexer::from(*tc)
+                .collect::<Result<Vec<Token>, Located<Error>>>()
+                .expect(tc);
+            assert_eq!(
+                actual,
+                vec![Token::Value(SimpleValue::LiteralString(expected))],
+                "test case: {}",
+                tc
+            );
+        }
+    }
+
+    #[test]
+    fn literal_string_terminates_at_first_delimiter() {
+        // A '"' followed by more '#' characters than the delimiter still
+        // terminates the string, leaving the remaining '#' characters to be
+        // lexed as the start of another literal string
+        let mut lexer = Lexer::from("#\"a\"## b\"#");
+        assert_eq!(
+            lexer.next().unwrap().unwrap(),
+            Token::Value(SimpleValue::LiteralString("a"))
+        );
+        assert_eq!(
+            lexer.next().unwrap(),
+            located_err(1, 7, Error::UnexpectedChar)
+        );
+    }
+
+    #[test]
+    fn literal_string_delimiter_too_long() {
+        let tc = "#####################\"a\"#####################";
+        let r = Lexer::from(tc).next().unwrap();
+        assert_eq!(
+            r,
+            located_err(
+                1,
+                1,
+                Error::StringLiteralDelimTooLong {
+                    len: 21,
+                    max_len: 20
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn string_unescaping() {
+        const TEST_CASES: &[(&str, &str)] = &[
+            ("no escapes", "no escapes"),
+            (r#"a \\ b \" c \' d"#, "a \\ b \" c ' d"),
+            (r"\n\t\r", "\n\t\r"),
+            (r"\u{7f}\u{1F600}\u{e9}", "\u{7f}\u{1F600}é"),
+            (r"\x41\x7f\xe9\x00", "A\u{7f}é\0"),
+            // Line continuations
+            ("line one \\\n    line two", "line one line two"),
+            ("line one \\\r\n\tline two", "line one line two"),
+            ("line one \\\rline two", "line one line two"),
+            ("one\\\n\\\ntwo", "onetwo"),
+            // Only the whitespace immediately following the continuation is
+            // removed
+            ("one \\\n  two\n  three", "one two\n  three"),
+        ];
+        for (tc, expected) in TEST_CASES {
+            assert_eq!(
+                unescape_regular_string(tc).unwrap(),
+                *expected,
+                "test case: {:?}",
+                tc
+            );
+        }
+        assert!(matches!(
+            unescape_regular_string("no escapes"),
+            Ok(Cow::Borrowed(_))
+        ));
+
+        const INVALID: &[&str] = &[
+            r"\a",
+            r"trailing \",
+            r"C:\path",
+            "\\ \n",
+            r"\u7f",
+            r"\u{}",
+            r"\u{7f",
+            r"\u{1234567}",
+            r"\u{d800}",
+            r"\u{xyz}",
+            r"\x",
+            r"\x4",
+            r"\x4g",
+            r"\x{41}",
+        ];
+        for tc in INVALID {
+            assert_eq!(
+                unescape_regular_string(tc),
+                Err(Error::InvalidEscape),
+                "test case: {:?}",
+                tc
+            );
+        }
+
+        // Escaping a string and parsing it back yields the original text
+        for s in ["\\ \"quoted\"\n\ttabbed\r\u{1}", "ends in \\", "\\\""] {
+            let src = format!("\"{}\"", crate::escape_regular_string(s));
+            let doc = Parser::from(src.as_str()).parse().unwrap();
+            assert_eq!(doc.value.as_str().unwrap(), s, "source: {}", src);
+        }
+    }
+
+    #[test]
+    fn line_continuation_parsing() {
+        let doc = Parser::from("\"line one \\\n    line two\"")
+            .parse()
+            .unwrap();
+        assert_eq!(doc.value.as_str().unwrap(), "line one line two");
+    }
+
+    #[test]
+    fn escaped_string_parsing() {
+        let doc = Parser::from(
+            r##"{
+    a "\x41\u{42}\tC"
+    b d"
+        \x44
+    "
+    c #"\x41"#
+}"##,
+        )
+        .parse()
+        .unwrap();
+        assert_eq!(doc.value.get("a").unwrap().as_str().unwrap(), "AB\tC");
+        assert_eq!(doc.value.get("b").unwrap().as_str().unwrap(), "D");
+        assert_eq!(doc.value.get("c").unwrap().as_str().unwrap(), "\\x41");
+
+        const INVALID: &[(&str, Located<Error>)] = &[
+            (
+                r#""\q""#,
+                Located {
+                    line: 1,
+                    col: 1,
+                    err: Error::InvalidEscape,
+                },
+            ),
+            (
+                "{\n  a d\"\\x4\"\n}",
+                Located {
+                    line: 2,
+                    col: 5,
+                    err: Error::InvalidEscape,
+                },
+            ),
+        ];
+        for (tc, expected) in INVALID {
+            let actual = Parser::from(*tc).parse().unwrap_err();
+            assert_eq!(actual, *expected, "test case: {}", tc);
+        }
+    }
+
+    #[test]
+    fn comma_lexing() {
+        let actual = Lexer::from("[1,2.5, true,\"a\",2000-01-01]")
+            .collect::<Result<Vec<Token>, Located<Error>>>()
+            .unwrap();
+        assert_eq!(
+            actual,
+            vec![
+                Token::ArrayStart,
+                Token::Value(SimpleValue::Number("1")),
+                Token::Comma,
+                Token::Value(SimpleValue::Number("2.5")),
+                Token::Comma,
+                Token::Value(SimpleValue::Bool(true)),
+                Token::Comma,
+                Token::Value(SimpleValue::String("a")),
+                Token::Comma,
+                Token::Value(SimpleValue::Date("2000-01-01")),
+                Token::ArrayEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn comma_separators() {
+        const TEST_CASES: &[&str] = &[
+            "[1, 2, 3]",
+            "[1, 2, 3,]",
+            "[1,2,3]",
+            "[\n  1,\n  2,\n  3,\n]",
+            "[1 2 3]",
+        ];
+        for tc in TEST_CASES {
+            let doc = Parser::from(*tc).parse().expect(tc);
+            assert_eq!(
+                doc.value,
+                Value::Array(vec![
+                    Value::Number(Number::Unsigned(1)),
+                    Value::Number(Number::Unsigned(2)),
+                    Value::Number(Number::Unsigned(3)),
+                ]),
+                "test case: {}",
+                tc
+            );
+        }
+
+        const OBJECT_CASES: &[&str] = &["{ a 1, b 2 }", "{ a 1, b 2, }", "{\n  a 1,\n  b 2\n}"];
+        for tc in OBJECT_CASES {
+            let doc = Parser::from(*tc).parse().expect(tc);
+            assert_eq!(doc.value.len(), Some(2), "test case: {}", tc);
+            assert_eq!(
+                doc.value.get("b"),
+                Some(&Value::Number(Number::Unsigned(2))),
+                "test case: {}",
+                tc
+            );
+        }
+    }
+
+    #[test]
+    fn post_property_docstrings() {
+        let doc = Parser::from(
+            r#"{
+    a true /// Documents a.
+    /// Documents b.
+    b [1, 2] /// Also documents b.
+    c { d null }, /// Documents c.
+    e 1
+}"#,
+        )
+        .parse()
+        .unwrap();
+        let docstrings = doc
+            .value
+            .object_entries()
+            .unwrap()
+            .iter()
+            .map(|prop| (prop.id, prop.docstring.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            docstrings,
+            vec![
+                ("a", vec![" Documents a.\n"]),
+                ("b", vec![" Documents b.\n", " Also documents b.\n"]),
+                ("c", vec![" Documents c.\n"]),
+                ("e", vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn readme_example() {
+        let readme = include_str!("../README.md");
+        let start = readme.find("```\n").unwrap() + 4;
+        let end = start + readme[start..].find("```").unwrap();
+        let doc = Parser::from(&readme[start..end]).parse().unwrap();
+        assert_eq!(doc.value.get("arr1").and_then(Value::len), Some(5));
+        assert_eq!(doc.value.get("objarr").and_then(Value::len), Some(3));
+        let datetime = doc.value.get_documented("datetime").unwrap();
+        assert_eq!(
+            datetime.docstring,
+            vec![" A date/time value in RFC3999 format.\n"]
+        );
+        assert_eq!(
+            datetime.value,
+            Value::DateTime("2000-01-01T00:00:00-05:00".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn escaped_backslash_before_quote() {
+        const TEST_CASES: &[(&str, &str)] = &[
+            (r#""a\\""#, r"a\\"),
+            (r#""C:\\dir\\""#, r"C:\\dir\\"),
+            (r#""\\\"""#, r#"\\\""#),
+            ("\"a\\\\\nb\"", "a\\\\\nb"),
+        ];
+        for (tc, expected) in TEST_CASES {
+            let actual = Lexer::from(*tc)
+                .collect::<Result<Vec<Token>, Located<Error>>>()
+                .expect(tc);
+            assert_eq!(
+                actual,
+                vec![Token::Value(SimpleValue::String(expected))],
+                "test case: {}",
+                tc
+            );
+        }
+        let r = Lexer::from(r#""a\""#)
+            .collect::<Result<Vec<Token>, Located<Error>>>()
+            .unwrap_err();
+        assert_eq!(r, Located::new(1, 1, Error::UnterminatedString));
+    }
+
+    #[test]
+    fn stale_peek() {
+        let mut lexer = Lexer::from("ab");
+        let peek = lexer.peek_char().unwrap();
+        lexer.advance(lexer.peek_char().unwrap()).unwrap();
+        assert_eq!(
+            lexer.advance(peek),
+            located_err(1, 2, Error::InvalidPeek { from: 0, pos: 1 })
+        );
+    }
+
+    proptest::proptest! {
+        #![proptest_config(proptest::prelude::ProptestConfig::with_cases(4096))]
+
+        // The lexer may fail on arbitrary input, but must neither panic nor
+        // loop forever. Since the lexer doesn't advance past errors, lexing
+        // stops at the first error.
+        #[test]
+        fn lexer_never_panics(s in "\\PC*") {
+            let opts = LexerOptions {
+                allow_directives: true,
+                single_quoted_strings: true,
+            };
+            let _ = Lexer::new(&s, opts).collect::<Result<Vec<Token>, Located<Error>>>();
+            let _ = Parser::from(s.as_str()).parse();
+        }
+
+        // Same as above, but biased towards characters that are significant
+        // to the lexer.
+        #[test]
+        fn lexer_never_panics_on_syntax(s in "[ \\t\\r\\n/*{}\\[\\],\"#'d@a-z0-9.:+\\-\\\\éTZ\u{FEFF}]*") {
+            let opts = LexerOptions {
+                allow_directives: true,
+                single_quoted_strings: true,
+            };
+            let _ = Lexer::new(&s, opts).collect::<Result<Vec<Token>, Located<Error>>>();
+            let _ = Parser::from(s.as_str()).parse();
+        }
+    }
 }
@@ -1,14 +1,26 @@
 //! Rust equivalents of Gunnyscript values.
 
-use alloc::vec::Vec;
+use alloc::{
+    borrow::Cow,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt, str::FromStr};
 
+use crate::{unescape_regular_string, Error, Number};
+
+/// The lines of a string, excluding their line endings. When parsing, `\n`,
+/// `\r\n` and a lone `\r` are all treated as line endings, and all other
+/// whitespace is preserved verbatim.
 pub type MultiLineString<'a> = Vec<&'a str>;
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct Document<'a> {
     pub docstring: MultiLineString<'a>,
     pub value: Value<'a>,
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum Value<'a> {
     Null,
     Bool(bool),
@@ -20,28 +32,256 @@ pub enum Value<'a> {
     Object(DocumentedProperties<'a>),
 }
 
-pub enum Number {
-    Float(f64),
-    Unsigned(u64),
-    Signed(i64),
+impl<'a> Value<'a> {
+    /// Returns the number of elements in an array, or the number of
+    /// properties in an object. Returns `None` for any other kind of value.
+    pub fn len(&self) -> Option<usize> {
+        match self {
+            Self::Array(values) => Some(values.len()),
+            Self::Object(props) => Some(props.len()),
+            _ => None,
+        }
+    }
+
+    /// Returns whether an array or object is empty. Returns `None` for any
+    /// other kind of value.
+    pub fn is_empty(&self) -> Option<bool> {
+        self.len().map(|len| len == 0)
+    }
+
+    /// Returns the value of the first property with the given ID if this is an
+    /// object.
+    pub fn get(&self, id: &str) -> Option<&Value<'a>> {
+        self.get_documented(id).map(|prop| &prop.value)
+    }
+
+    /// Returns the first property (including its docstring) with the given ID
+    /// if this is an object.
+    pub fn get_documented(&self, id: &str) -> Option<&DocumentedProperty<'a>> {
+        match self {
+            Self::Object(props) => props.iter().find(|prop| prop.id == id),
+            _ => None,
+        }
+    }
+
+    /// Returns the element at index `i` if this is an array.
+    pub fn index(&self, i: usize) -> Option<&Value<'a>> {
+        match self {
+            Self::Array(values) => values.get(i),
+            _ => None,
+        }
+    }
+
+    /// Returns the properties of an object, in the order in which they were
+    /// defined.
+    pub fn object_entries(&self) -> Option<&[DocumentedProperty<'a>]> {
+        match self {
+            Self::Object(props) => Some(props),
+            _ => None,
+        }
+    }
+
+    /// Stably sorts the properties of an object by their IDs, carrying along
+    /// their docstrings. Nested objects are left as is. Does nothing for any
+    /// other kind of value.
+    pub fn sort_object_keys(&mut self) {
+        if let Self::Object(props) = self {
+            props.sort_by(|a, b| a.id.cmp(b.id));
+        }
+    }
+
+    /// Reorders the properties of an object such that those with the given
+    /// IDs come first, in the given order, followed by all other properties
+    /// in their existing order. IDs in `order` that don't refer to any
+    /// property are ignored. Does nothing for any other kind of value.
+    pub fn reorder(&mut self, order: &[&str]) {
+        if let Self::Object(props) = self {
+            props.sort_by_key(|prop| {
+                order
+                    .iter()
+                    .position(|id| *id == prop.id)
+                    .unwrap_or(order.len())
+            });
+        }
+    }
+
+    /// Returns the text of a string value, with its lines joined by `\n`.
+    /// Dedent strings are trimmed and dedented (see [`dedent`]), and escape
+    /// sequences in non-literal strings are then decoded (see
+    /// [`unescape_regular_string`]). Returns `None` for any other kind of
+    /// value.
+    ///
+    /// Single-line regular strings without escape sequences are borrowed from
+    /// the source, while all other strings are allocated. Strings with invalid
+    /// escape sequences, which the parser rejects, are returned undecoded.
+    pub fn as_str(&self) -> Option<Cow<'a, str>> {
+        let (s, text) = match self {
+            Self::String(ValueString::Regular(s)) => (
+                s,
+                match s.lines() {
+                    [] => Cow::Borrowed(""),
+                    [line] => Cow::Borrowed(*line),
+                    lines => Cow::Owned(lines.join("\n")),
+                },
+            ),
+            Self::String(ValueString::Dedent(s)) => (s, Cow::Owned(dedent(s.lines()))),
+            _ => return None,
+        };
+        Some(match s {
+            MaybeLiteralString::NonLiteral(_) => unescape(text),
+            MaybeLiteralString::Literal(_) => text,
+        })
+    }
+
+    /// Returns the ID, docstring (joined using [`join_docstring`]) and value
+    /// of each property of an object, in the order in which they were defined.
+    pub fn documented_entries(
+        &self,
+    ) -> Option<impl Iterator<Item = (&'a str, String, &Value<'a>)> + '_> {
+        self.object_entries().map(|props| {
+            props
+                .iter()
+                .map(|prop| (prop.id, join_docstring(&prop.docstring), &prop.value))
+        })
+    }
 }
 
+// Decodes the escape sequences in the text of a non-literal string, returning
+// the text as is if it contains invalid escape sequences.
+fn unescape(text: Cow<'_, str>) -> Cow<'_, str> {
+    match text {
+        Cow::Borrowed(s) => unescape_regular_string(s).unwrap_or(Cow::Borrowed(s)),
+        Cow::Owned(s) => {
+            let decoded = match unescape_regular_string(&s) {
+                Ok(Cow::Owned(decoded)) => Some(decoded),
+                _ => None,
+            };
+            Cow::Owned(decoded.unwrap_or(s))
+        }
+    }
+}
+
+/// Joins the lines of a docstring into a single string. A single leading
+/// space (conventionally following the `///`) is removed from each line, and
+/// lines are separated by `\n` with no trailing line ending.
+pub fn join_docstring(lines: &[&str]) -> String {
+    let mut s = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            s.push('\n');
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        s.push_str(line.strip_prefix(' ').unwrap_or(line));
+    }
+    s
+}
+
+/// Collects the docstrings of all documented properties in the document,
+/// including those nested within objects and arrays, in the order in which
+/// they were defined. Each docstring is joined using [`join_docstring`] and
+/// paired with the dotted path of its property. Properties without a
+/// docstring are omitted, as is the docstring of the document itself.
+///
+/// Path segments are property IDs for object properties and zero-based
+/// indices for array elements. For example, the `title` property of the first
+/// element of the `posts` array has the path `posts.0.title`.
+pub fn collect_docstrings(doc: &Document) -> Vec<(String, String)> {
+    let mut docstrings = Vec::new();
+    collect_value_docstrings(&doc.value, "", &mut docstrings);
+    docstrings
+}
+
+fn collect_value_docstrings(value: &Value, path: &str, docstrings: &mut Vec<(String, String)>) {
+    let child_path = |segment: &str| {
+        if path.is_empty() {
+            segment.to_string()
+        } else {
+            alloc::format!("{}.{}", path, segment)
+        }
+    };
+    match value {
+        Value::Object(props) => {
+            for prop in props {
+                let prop_path = child_path(prop.id);
+                if !prop.docstring.is_empty() {
+                    docstrings.push((prop_path.clone(), join_docstring(&prop.docstring)));
+                }
+                collect_value_docstrings(&prop.value, &prop_path, docstrings);
+            }
+        }
+        Value::Array(values) => {
+            for (i, value) in values.iter().enumerate() {
+                collect_value_docstrings(value, &child_path(&i.to_string()), docstrings);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum ValueString<'a> {
     Regular(MaybeLiteralString<'a>),
     Dedent(MaybeLiteralString<'a>),
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum MaybeLiteralString<'a> {
     NonLiteral(MultiLineString<'a>),
     Literal(MultiLineString<'a>),
 }
 
+impl<'a> MaybeLiteralString<'a> {
+    /// Returns the lines of the string, regardless of whether it is a literal.
+    pub fn lines(&self) -> &[&'a str] {
+        match self {
+            Self::NonLiteral(lines) | Self::Literal(lines) => lines,
+        }
+    }
+}
+
+/// Applies the dedent rule for dedent strings to the given lines, joining the
+/// result with `\n`.
+///
+/// Blank (whitespace-only) lines at the start and end of the string are
+/// removed. The indentation (spaces and tabs) of the first remaining line is
+/// then removed from the start of every line. Lines that are indented less
+/// than the first line only have their own indentation removed, while lines
+/// that are indented more keep their additional indentation.
+pub fn dedent(lines: &[&str]) -> String {
+    let is_blank = |line: &&str| line.trim().is_empty();
+    let (start, end) = match (
+        lines.iter().position(|l| !is_blank(l)),
+        lines.iter().rposition(|l| !is_blank(l)),
+    ) {
+        (Some(start), Some(end)) => (start, end + 1),
+        _ => return String::new(),
+    };
+    let lines = &lines[start..end];
+    let first = lines[0];
+    let indent = &first[..first.len() - first.trim_start_matches([' ', '\t']).len()];
+    let mut s = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            s.push('\n');
+        }
+        let n = line
+            .bytes()
+            .zip(indent.bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+        s.push_str(&line[n..]);
+    }
+    s
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Date {
     pub year: u16,
     pub month: u8,
     pub day: u8,
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct DateTime {
     pub year: u16,
     pub month: u8,
@@ -55,10 +295,602 @@ pub struct DateTime {
     pub offset_mins: u8,
 }
 
+/// Options to control how leniently [`DateTime`]s are parsed. By default, both
+/// end-of-day times and leap seconds are rejected.
+#[derive(Debug, Clone, Default)]
+pub struct DateTimeParseOptions {
+    /// If set, an end-of-day time of `24:00:00` is accepted and normalized to
+    /// `00:00:00` on the following day. Fractional seconds must be zero.
+    pub allow_end_of_day: bool,
+    /// If set, a leap second (`:60`, only valid in the last minute of an hour)
+    /// is accepted and clamped to `:59.999999999`, as [`DateTime`] cannot
+    /// represent it.
+    pub allow_leap_second: bool,
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl fmt::Display for DateTime {
+    /// Formats the date/time according to RFC 3339, only including fractional
+    /// seconds where necessary.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.min, self.sec
+        )?;
+        if self.nanos > 0 {
+            let mut nanos = self.nanos;
+            let mut width = 9;
+            while nanos.is_multiple_of(10) {
+                nanos /= 10;
+                width -= 1;
+            }
+            write!(f, ".{:0width$}", nanos, width = width)?;
+        }
+        if self.offset_positive && self.offset_hours == 0 && self.offset_mins == 0 {
+            write!(f, "Z")
+        } else {
+            write!(
+                f,
+                "{}{:02}:{:02}",
+                if self.offset_positive { '+' } else { '-' },
+                self.offset_hours,
+                self.offset_mins
+            )
+        }
+    }
+}
+
+impl FromStr for Date {
+    type Err = Error;
+
+    /// Parses a date of the form `YYYY-MM-DD`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_date(s.as_bytes()).ok_or(Error::InvalidDate)
+    }
+}
+
+impl FromStr for DateTime {
+    type Err = Error;
+
+    /// Parses an [RFC 3339](https://www.rfc-editor.org/rfc/rfc3339) date/time,
+    /// e.g. `2022-01-02T03:04:05.678+02:00`. The offset may also be written
+    /// without a colon, e.g. `2022-01-02T03:04:05-0500`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with_options(s, DateTimeParseOptions::default())
+    }
+}
+
+impl DateTime {
+    /// Parses an [RFC 3339](https://www.rfc-editor.org/rfc/rfc3339) date/time,
+    /// optionally allowing end-of-day times and leap seconds (see
+    /// [`DateTimeParseOptions`]).
+    pub fn parse_with_options(s: &str, opts: DateTimeParseOptions) -> Result<Self, Error> {
+        parse_datetime(s.as_bytes(), &opts).ok_or(Error::InvalidDateTime)
+    }
+}
+
 pub type DocumentedProperties<'a> = Vec<DocumentedProperty<'a>>;
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct DocumentedProperty<'a> {
     pub docstring: MultiLineString<'a>,
     pub id: &'a str,
     pub value: Value<'a>,
 }
+
+fn parse_date(s: &[u8]) -> Option<Date> {
+    if s.len() != 10 || s[4] != b'-' || s[7] != b'-' {
+        return None;
+    }
+    let year = parse_digits(&s[..4])? as u16;
+    let month = parse_digits(&s[5..7])? as u8;
+    let day = parse_digits(&s[8..10])? as u8;
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+    Some(Date { year, month, day })
+}
+
+fn parse_datetime(s: &[u8], opts: &DateTimeParseOptions) -> Option<DateTime> {
+    if s.len() < 20 || !matches!(s[10], b'T' | b't') || s[13] != b':' || s[16] != b':' {
+        return None;
+    }
+    let mut date = parse_date(&s[..10])?;
+    let mut hour = parse_digits(&s[11..13])? as u8;
+    let min = parse_digits(&s[14..16])? as u8;
+    let mut sec = parse_digits(&s[17..19])? as u8;
+    let end_of_day = opts.allow_end_of_day && hour == 24 && min == 0 && sec == 0;
+    let leap_second = opts.allow_leap_second && min == 59 && sec == 60;
+    if (hour > 23 && !end_of_day) || min > 59 || (sec > 59 && !leap_second) {
+        return None;
+    }
+
+    let mut rest = &s[19..];
+    let mut nanos = 0;
+    if rest[0] == b'.' {
+        let frac_len = rest[1..].iter().take_while(|b| b.is_ascii_digit()).count();
+        if frac_len == 0 || frac_len > 9 {
+            return None;
+        }
+        nanos = parse_digits(&rest[1..=frac_len])? as u64 * 10_u64.pow(9 - frac_len as u32);
+        rest = &rest[frac_len + 1..];
+    }
+    if end_of_day {
+        if nanos > 0 {
+            return None;
+        }
+        date = next_day(&date)?;
+        hour = 0;
+    }
+    if leap_second {
+        sec = 59;
+        nanos = 999_999_999;
+    }
+
+    let (offset_positive, offset_hours, offset_mins) = match rest {
+        [b'Z' | b'z'] => (true, 0, 0),
+        [sign @ (b'+' | b'-'), h1, h2, b':', m1, m2] | [sign @ (b'+' | b'-'), h1, h2, m1, m2] => {
+            let offset_hours = parse_digits(&[*h1, *h2])? as u8;
+            let offset_mins = parse_digits(&[*m1, *m2])? as u8;
+            if offset_hours > 23 || offset_mins > 59 {
+                return None;
+            }
+            (*sign == b'+', offset_hours, offset_mins)
+        }
+        _ => return None,
+    };
+
+    Some(DateTime {
+        year: date.year,
+        month: date.month,
+        day: date.day,
+        hour,
+        min,
+        sec,
+        nanos,
+        offset_positive,
+        offset_hours,
+        offset_mins,
+    })
+}
+
+// Returns the day after the given date, or `None` if it falls after the year
+// 9999.
+fn next_day(date: &Date) -> Option<Date> {
+    let Date { year, month, day } = *date;
+    if day < days_in_month(year, month) {
+        Some(Date {
+            year,
+            month,
+            day: day + 1,
+        })
+    } else if month < 12 {
+        Some(Date {
+            year,
+            month: month + 1,
+            day: 1,
+        })
+    } else if year < 9999 {
+        Some(Date {
+            year: year + 1,
+            month: 1,
+            day: 1,
+        })
+    } else {
+        None
+    }
+}
+
+fn parse_digits(s: &[u8]) -> Option<u32> {
+    s.iter().try_fold(0_u32, |acc, b| {
+        b.is_ascii_digit().then(|| acc * 10 + (b - b'0') as u32)
+    })
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::{string::ToString, vec};
+
+    use super::*;
+    use crate::Parser;
+
+    fn object<'a>() -> Value<'a> {
+        Value::Object(vec![
+            DocumentedProperty {
+                docstring: vec![" The title of the post.\n"],
+                id: "title",
+                value: Value::String(ValueString::Regular(MaybeLiteralString::NonLiteral(vec![
+                    "Hello world",
+                ]))),
+            },
+            DocumentedProperty {
+                docstring: vec![],
+                id: "tags",
+                value: Value::Array(vec![Value::Bool(true), Value::Null]),
+            },
+        ])
+    }
+
+    #[test]
+    fn collection_len() {
+        let obj = object();
+        assert_eq!(obj.len(), Some(2));
+        assert_eq!(obj.is_empty(), Some(false));
+        assert_eq!(obj.get("tags").unwrap().len(), Some(2));
+        assert_eq!(Value::Array(vec![]).is_empty(), Some(true));
+        assert_eq!(Value::Null.len(), None);
+        assert_eq!(Value::Bool(false).is_empty(), None);
+    }
+
+    #[test]
+    fn object_property_access() {
+        let obj = object();
+        let title = obj.get_documented("title").unwrap();
+        assert_eq!(title.docstring, vec![" The title of the post.\n"]);
+        assert_eq!(
+            title.value,
+            Value::String(ValueString::Regular(MaybeLiteralString::NonLiteral(vec![
+                "Hello world"
+            ])))
+        );
+        assert_eq!(obj.get("title"), Some(&title.value));
+        assert!(obj.get_documented("tags").unwrap().docstring.is_empty());
+        assert_eq!(obj.get("missing"), None);
+        assert_eq!(obj.index(0), None);
+    }
+
+    #[test]
+    fn array_element_access() {
+        let obj = object();
+        let tags = obj.get("tags").unwrap();
+        assert_eq!(tags.index(0), Some(&Value::Bool(true)));
+        assert_eq!(tags.index(1), Some(&Value::Null));
+        assert_eq!(tags.index(2), None);
+        assert_eq!(tags.get("title"), None);
+    }
+
+    #[test]
+    fn date_parsing() {
+        assert_eq!(
+            "2022-01-02".parse::<Date>().unwrap(),
+            Date {
+                year: 2022,
+                month: 1,
+                day: 2
+            }
+        );
+        assert_eq!("2024-02-29".parse::<Date>().unwrap().day, 29);
+        const INVALID: &[&str] = &[
+            "",
+            "2022-1-02",
+            "2022-01-02T",
+            "2022/01/02",
+            "2022-00-01",
+            "2022-13-01",
+            "2022-01-32",
+            "2023-02-29",
+            "1900-02-29",
+        ];
+        for tc in INVALID {
+            assert_eq!(
+                tc.parse::<Date>(),
+                Err(Error::InvalidDate),
+                "test case: {}",
+                tc
+            );
+        }
+    }
+
+    #[test]
+    fn datetime_parsing() {
+        assert_eq!(
+            "2022-01-02T03:04:05.678+02:30".parse::<DateTime>().unwrap(),
+            DateTime {
+                year: 2022,
+                month: 1,
+                day: 2,
+                hour: 3,
+                min: 4,
+                sec: 5,
+                nanos: 678_000_000,
+                offset_positive: true,
+                offset_hours: 2,
+                offset_mins: 30,
+            }
+        );
+        let dt = "1999-12-31t23:59:59z".parse::<DateTime>().unwrap();
+        assert_eq!((dt.hour, dt.min, dt.sec, dt.nanos), (23, 59, 59, 0));
+        assert!(dt.offset_positive);
+        let dt = "1999-12-31T23:59:59.000000001-05:00"
+            .parse::<DateTime>()
+            .unwrap();
+        assert_eq!(dt.nanos, 1);
+        assert!(!dt.offset_positive);
+        assert_eq!(dt.offset_hours, 5);
+        let dt = "2000-01-01T00:00:00-0530".parse::<DateTime>().unwrap();
+        assert!(!dt.offset_positive);
+        assert_eq!((dt.offset_hours, dt.offset_mins), (5, 30));
+
+        const INVALID: &[&str] = &[
+            "2022-01-02",
+            "2022-01-02T03:04:05",
+            "2022-01-02 03:04:05Z",
+            "2022-01-02T24:00:00Z",
+            "2022-01-02T03:60:00Z",
+            "2022-01-02T03:04:60Z",
+            "2022-01-02T03:04:05.Z",
+            "2022-01-02T03:04:05.1234567890Z",
+            "2022-01-02T03:04:05+020",
+            "2022-01-02T03:04:05+02:0",
+            "2022-01-02T03:04:05+24:00",
+            "2022-01-02T03:04:05+2400",
+        ];
+        for tc in INVALID {
+            assert_eq!(
+                tc.parse::<DateTime>(),
+                Err(Error::InvalidDateTime),
+                "test case: {}",
+                tc
+            );
+        }
+    }
+
+    #[test]
+    fn datetime_end_of_day_and_leap_seconds() {
+        const END_OF_DAY: &[(&str, &str)] = &[
+            ("2022-01-02T24:00:00Z", "2022-01-03T00:00:00Z"),
+            ("2022-02-28T24:00:00+02:00", "2022-03-01T00:00:00+02:00"),
+            ("2024-02-28T24:00:00Z", "2024-02-29T00:00:00Z"),
+            ("2022-12-31T24:00:00.000Z", "2023-01-01T00:00:00Z"),
+        ];
+        const LEAP_SECOND: &[(&str, &str)] = &[
+            ("2016-12-31T23:59:60Z", "2016-12-31T23:59:59.999999999Z"),
+            (
+                "2016-12-31T18:59:60.5-05:00",
+                "2016-12-31T18:59:59.999999999-05:00",
+            ),
+        ];
+        let opts = DateTimeParseOptions {
+            allow_end_of_day: true,
+            allow_leap_second: true,
+        };
+        for (tc, expected) in END_OF_DAY.iter().chain(LEAP_SECOND) {
+            // Rejected by default
+            assert_eq!(
+                tc.parse::<DateTime>(),
+                Err(Error::InvalidDateTime),
+                "test case: {}",
+                tc
+            );
+            assert_eq!(
+                DateTime::parse_with_options(tc, opts.clone())
+                    .unwrap()
+                    .to_string(),
+                *expected,
+                "test case: {}",
+                tc
+            );
+        }
+
+        // Each policy only applies to its own case
+        let end_of_day_only = DateTimeParseOptions {
+            allow_end_of_day: true,
+            ..Default::default()
+        };
+        assert!(DateTime::parse_with_options(LEAP_SECOND[0].0, end_of_day_only).is_err());
+        let leap_second_only = DateTimeParseOptions {
+            allow_leap_second: true,
+            ..Default::default()
+        };
+        assert!(DateTime::parse_with_options(END_OF_DAY[0].0, leap_second_only).is_err());
+
+        const INVALID: &[&str] = &[
+            "2022-01-02T24:00:01Z",
+            "2022-01-02T24:01:00Z",
+            "2022-01-02T24:00:00.1Z",
+            "9999-12-31T24:00:00Z",
+            "2022-01-02T03:04:60Z",
+            "2022-01-02T03:59:61Z",
+        ];
+        for tc in INVALID {
+            assert_eq!(
+                DateTime::parse_with_options(tc, opts.clone()),
+                Err(Error::InvalidDateTime),
+                "test case: {}",
+                tc
+            );
+        }
+    }
+
+    #[test]
+    fn datetime_formatting() {
+        const TEST_CASES: &[&str] = &[
+            "2022-01-02T03:04:05Z",
+            "2022-01-02T03:04:05.5Z",
+            "2022-01-02T03:04:05.000000001-05:00",
+            "2022-01-02T03:04:05.678+02:30",
+        ];
+        for tc in TEST_CASES {
+            assert_eq!(tc.parse::<DateTime>().unwrap().to_string(), *tc);
+        }
+        assert_eq!(
+            "0999-01-02".parse::<Date>().unwrap().to_string(),
+            "0999-01-02"
+        );
+    }
+
+    #[test]
+    fn documented_entries() {
+        let doc = Parser::from(
+            r#"{
+    /// The title of the post.
+    title "Hello"
+    draft false
+    /// Tags for the post.
+    ///
+    ///   Indented line.
+    tags []
+}"#,
+        )
+        .parse()
+        .unwrap();
+        assert_eq!(doc.value.object_entries().unwrap().len(), 3);
+        let entries = doc.value.documented_entries().unwrap().collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            vec![
+                (
+                    "title",
+                    "The title of the post.".to_string(),
+                    &Value::String(ValueString::Regular(MaybeLiteralString::NonLiteral(vec![
+                        "Hello"
+                    ])))
+                ),
+                ("draft", "".to_string(), &Value::Bool(false)),
+                (
+                    "tags",
+                    "Tags for the post.\n\n  Indented line.".to_string(),
+                    &Value::Array(vec![])
+                ),
+            ]
+        );
+        assert!(Value::Null.object_entries().is_none());
+        assert!(Value::Array(vec![]).documented_entries().is_none());
+    }
+
+    #[test]
+    fn docstring_collection() {
+        let doc = Parser::from(
+            r#"/// The document.
+{
+    /// The title of the site.
+    title "Hello"
+    undocumented null
+    /// Site authors.
+    authors [
+        {
+            /// The author's name.
+            ///
+            /// Shown on each post.
+            name "A"
+        }
+        {
+            name "B"
+            /// Contact details.
+            contact {
+                /// An email address.
+                email "b@example.com"
+            }
+        }
+    ]
+}"#,
+        )
+        .parse()
+        .unwrap();
+        let docstrings = collect_docstrings(&doc);
+        let expected = [
+            ("title", "The title of the site."),
+            ("authors", "Site authors."),
+            (
+                "authors.0.name",
+                "The author's name.\n\nShown on each post.",
+            ),
+            ("authors.1.contact", "Contact details."),
+            ("authors.1.contact.email", "An email address."),
+        ]
+        .map(|(path, docstring)| (path.to_string(), docstring.to_string()));
+        assert_eq!(docstrings, expected);
+    }
+
+    #[test]
+    fn string_text() {
+        let single = Value::String(ValueString::Regular(MaybeLiteralString::NonLiteral(vec![
+            "Hello",
+        ])));
+        assert!(matches!(single.as_str(), Some(Cow::Borrowed("Hello"))));
+
+        let multi = Value::String(ValueString::Regular(MaybeLiteralString::Literal(vec![
+            "first", "  second", "",
+        ])));
+        let s = multi.as_str().unwrap();
+        assert!(matches!(s, Cow::Owned(_)));
+        assert_eq!(s, "first\n  second\n");
+
+        let doc = Parser::from(
+            "d\"\n        First line.\n\n            Indented.\n      Outdented.\n        Last line.\n    \"",
+        )
+        .parse()
+        .unwrap();
+        let s = doc.value.as_str().unwrap();
+        assert!(matches!(s, Cow::Owned(_)));
+        assert_eq!(s, "First line.\n\n    Indented.\nOutdented.\nLast line.");
+
+        assert_eq!(dedent(&["", "  ", ""]), "");
+        assert_eq!(Value::Null.as_str(), None);
+        assert_eq!(Value::Array(vec![]).as_str(), None);
+    }
+
+    #[test]
+    fn object_key_ordering() {
+        let mut value = Parser::from(
+            r#"{
+    /// The title.
+    title "Hello"
+    draft false
+    /// The author.
+    author { name "A" id null }
+    date null
+}"#,
+        )
+        .parse()
+        .unwrap()
+        .value;
+        fn ids<'a>(value: &Value<'a>) -> Vec<&'a str> {
+            value
+                .object_entries()
+                .unwrap()
+                .iter()
+                .map(|prop| prop.id)
+                .collect()
+        }
+
+        value.sort_object_keys();
+        assert_eq!(ids(&value), vec!["author", "date", "draft", "title"]);
+        // Docstrings move along with their properties, and nested objects
+        // are untouched
+        let author = value.get_documented("author").unwrap();
+        assert_eq!(author.docstring, vec![" The author.\n"]);
+        assert_eq!(ids(&author.value), vec!["name", "id"]);
+        assert_eq!(
+            value.get_documented("title").unwrap().docstring,
+            vec![" The title.\n"]
+        );
+
+        value.reorder(&["title", "missing", "date"]);
+        assert_eq!(ids(&value), vec!["title", "date", "author", "draft"]);
+        value.reorder(&[]);
+        assert_eq!(ids(&value), vec!["title", "date", "author", "draft"]);
+
+        let mut array = Value::Array(vec![Value::Null]);
+        array.sort_object_keys();
+        array.reorder(&["a"]);
+        assert_eq!(array, Value::Array(vec![Value::Null]));
+    }
+}
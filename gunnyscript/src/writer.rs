@@ -0,0 +1,623 @@
+//! Serialization of Gunnyscript documents.
+
+use alloc::{string::String, vec::Vec};
+use core::fmt::{self, Write};
+
+use crate::{
+    parser::MAX_LITERAL_HASHES, Document, DocumentedProperty, Error, MaybeLiteralString, Number,
+    Value, ValueString,
+};
+
+/// Options to control the formatting of serialized documents.
+#[derive(Debug, Clone)]
+pub struct WriterOptions {
+    /// The number of spaces by which to indent each level of nesting.
+    pub indent: usize,
+    /// Whether or not to emit docstrings.
+    pub docstrings: bool,
+    /// Whether or not to sort object properties by their IDs. If not set,
+    /// properties are emitted in the order in which they were defined.
+    pub sort_keys: bool,
+}
+
+impl Default for WriterOptions {
+    fn default() -> Self {
+        Self {
+            indent: 2,
+            docstrings: true,
+            sort_keys: false,
+        }
+    }
+}
+
+/// Serializes the given document to a string. See [`write_document`].
+pub fn to_string(doc: &Document, opts: &WriterOptions) -> Result<String, Error> {
+    let mut s = String::new();
+    write_document(&mut s, doc, opts)?;
+    Ok(s)
+}
+
+/// Serializes the given document to the given writer.
+///
+/// Fails with [`Error::NonFiniteNumber`] if the document contains an infinite
+/// or NaN float, or with [`Error::StringLiteralDelimTooLong`] if it contains a
+/// dedented literal string that would need a delimiter longer than the lexer
+/// allows. In either case nothing is written. Regular literal strings that
+/// would need such a delimiter are written as escaped non-literal strings
+/// instead.
+pub fn write_document<W: Write>(
+    w: &mut W,
+    doc: &Document,
+    opts: &WriterOptions,
+) -> Result<(), Error> {
+    check_writable(&doc.value)?;
+    let mut writer = Writer { w, opts };
+    writer
+        .write_docstring(&doc.docstring, 0)
+        .map_err(Error::Fmt)?;
+    writer.write_value(&doc.value, 0).map_err(Error::Fmt)?;
+    writer.w.write_char('\n').map_err(Error::Fmt)
+}
+
+// Ensures that all floats and dedented literal strings within the given value
+// can be written in a form that parses back to the same value.
+fn check_writable(value: &Value) -> Result<(), Error> {
+    match value {
+        Value::Number(Number::Float(f)) if !f.is_finite() => Err(Error::NonFiniteNumber),
+        Value::String(ValueString::Dedent(MaybeLiteralString::Literal(lines))) => {
+            match literal_hashes(lines) {
+                len if len > MAX_LITERAL_HASHES => Err(Error::StringLiteralDelimTooLong {
+                    len,
+                    max_len: MAX_LITERAL_HASHES,
+                }),
+                _ => Ok(()),
+            }
+        }
+        Value::Array(values) => values.iter().try_for_each(check_writable),
+        Value::Object(props) => props
+            .iter()
+            .try_for_each(|prop| check_writable(&prop.value)),
+        _ => Ok(()),
+    }
+}
+
+// The number of `#` characters needed to delimit a literal string with the
+// given lines.
+fn literal_hashes(lines: &[&str]) -> usize {
+    lines
+        .iter()
+        .map(|line| choose_literal_hashes(line))
+        .max()
+        .unwrap_or(1)
+}
+
+struct Writer<'w, W> {
+    w: &'w mut W,
+    opts: &'w WriterOptions,
+}
+
+impl<'w, W: Write> Writer<'w, W> {
+    fn write_indent(&mut self, depth: usize) -> fmt::Result {
+        for _ in 0..depth * self.opts.indent {
+            self.w.write_char(' ')?;
+        }
+        Ok(())
+    }
+
+    fn write_docstring(&mut self, lines: &[&str], depth: usize) -> fmt::Result {
+        if !self.opts.docstrings {
+            return Ok(());
+        }
+        for line in lines {
+            self.write_indent(depth)?;
+            writeln!(self.w, "///{}", line.trim_end_matches(['\r', '\n']))?;
+        }
+        Ok(())
+    }
+
+    fn write_value(&mut self, value: &Value, depth: usize) -> fmt::Result {
+        match value {
+            Value::Null => self.w.write_str("null"),
+            Value::Bool(b) => write!(self.w, "{}", b),
+            Value::Number(n) => write!(self.w, "{}", n),
+            Value::String(s) => self.write_string(s),
+            Value::Date(d) => write!(self.w, "{}", d),
+            Value::DateTime(dt) => write!(self.w, "{}", dt),
+            Value::Array(values) => self.write_array(values, depth),
+            Value::Object(props) => self.write_object(props, depth),
+        }
+    }
+
+    fn write_string(&mut self, s: &ValueString) -> fmt::Result {
+        let s = match s {
+            ValueString::Regular(s) => s,
+            ValueString::Dedent(s) => {
+                self.w.write_char('d')?;
+                s
+            }
+        };
+        match s {
+            MaybeLiteralString::NonLiteral(lines) => {
+                self.w.write_char('"')?;
+                self.write_escaped_lines(lines)?;
+                self.w.write_char('"')
+            }
+            MaybeLiteralString::Literal(lines) => {
+                let hashes = literal_hashes(lines);
+                // Only possible for regular strings (see `check_writable`),
+                // which can be escaped instead
+                if hashes > MAX_LITERAL_HASHES {
+                    return write!(self.w, "\"{}\"", escape_regular_string(&lines.join("\n")));
+                }
+                self.write_hashes(hashes)?;
+                self.w.write_char('"')?;
+                self.write_lines(lines)?;
+                self.w.write_char('"')?;
+                self.write_hashes(hashes)
+            }
+        }
+    }
+
+    fn write_lines(&mut self, lines: &[&str]) -> fmt::Result {
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                self.w.write_char('\n')?;
+            }
+            self.w.write_str(line)?;
+        }
+        Ok(())
+    }
+
+    // Writes the lines of a non-literal string, which are kept as written in
+    // the source, escaping any double quotes that aren't already escaped. These
+    // can occur in single-quoted strings (see
+    // `LexerOptions::single_quoted_strings`), and would otherwise terminate the
+    // string early.
+    fn write_escaped_lines(&mut self, lines: &[&str]) -> fmt::Result {
+        // Whether the previous character was an unescaped backslash, which
+        // carries over to the next line for line continuations
+        let mut escaping = false;
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                self.w.write_char('\n')?;
+                escaping = false;
+            }
+            for c in line.chars() {
+                if c == '"' && !escaping {
+                    self.w.write_char('\\')?;
+                }
+                self.w.write_char(c)?;
+                escaping = c == '\\' && !escaping;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_hashes(&mut self, count: usize) -> fmt::Result {
+        for _ in 0..count {
+            self.w.write_char('#')?;
+        }
+        Ok(())
+    }
+
+    fn write_array(&mut self, values: &[Value], depth: usize) -> fmt::Result {
+        if values.is_empty() {
+            return self.w.write_str("[]");
+        }
+        self.w.write_str("[\n")?;
+        for (i, value) in values.iter().enumerate() {
+            self.write_indent(depth + 1)?;
+            self.write_value(value, depth + 1)?;
+            if i + 1 < values.len() {
+                self.w.write_char(',')?;
+            }
+            self.w.write_char('\n')?;
+        }
+        self.write_indent(depth)?;
+        self.w.write_char(']')
+    }
+
+    fn write_object(&mut self, props: &[DocumentedProperty], depth: usize) -> fmt::Result {
+        if props.is_empty() {
+            return self.w.write_str("{}");
+        }
+        let mut props = props.iter().collect::<Vec<_>>();
+        if self.opts.sort_keys {
+            props.sort_by_key(|prop| prop.id);
+        }
+        self.w.write_str("{\n")?;
+        for prop in props {
+            self.write_docstring(&prop.docstring, depth + 1)?;
+            self.write_indent(depth + 1)?;
+            write!(self.w, "{} ", prop.id)?;
+            self.write_value(&prop.value, depth + 1)?;
+            self.w.write_char('\n')?;
+        }
+        self.write_indent(depth)?;
+        self.w.write_char('}')
+    }
+}
+
+/// Escapes the given text such that it can be enclosed in double quotes as a
+/// regular Gunnyscript string. Backslashes, double quotes, newlines, tabs and
+/// carriage returns are escaped as `\\`, `\"`, `\n`, `\t` and `\r`
+/// respectively, and all other control characters (below U+0020, as well as
+/// U+007F) as `\u{XX}`.
+pub fn escape_regular_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            c if c.is_ascii_control() => {
+                // Writing to a string cannot fail
+                write!(escaped, "\\u{{{:02x}}}", c as u32).unwrap();
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Quotes the given text as a Gunnyscript string, choosing between a regular
+/// string (escaped using [`escape_regular_string`]) and a literal string
+/// (delimited using [`choose_literal_hashes`]).
+///
+/// The literal form is used for text containing backslashes or double quotes,
+/// which would otherwise have to be escaped, as long as it doesn't contain
+/// any control characters other than newlines and tabs. Those would either
+/// be unreadable verbatim or, in the case of carriage returns, not read back
+/// as written, since line endings are normalized when parsing. Text that
+/// would need a literal delimiter longer than the lexer allows is always
+/// escaped.
+pub fn quote_string(s: &str) -> String {
+    let needs_escaping = s.contains(['\\', '"']);
+    let literal_safe = !s
+        .chars()
+        .any(|c| c.is_ascii_control() && c != '\n' && c != '\t');
+    let hashes = choose_literal_hashes(s);
+    if needs_escaping && literal_safe && hashes <= MAX_LITERAL_HASHES {
+        let hashes = "#".repeat(hashes);
+        alloc::format!("{}\"{}\"{}", hashes, s, hashes)
+    } else {
+        alloc::format!("\"{}\"", escape_regular_string(s))
+    }
+}
+
+/// Computes the minimum number of `#` characters needed to delimit a literal
+/// string with the given content, i.e. one more than the longest run of `#`
+/// characters following a `"` in the content (and at least one).
+pub fn choose_literal_hashes(s: &str) -> usize {
+    let mut hashes = 1;
+    let mut rest = s;
+    while let Some(i) = rest.find('"') {
+        rest = &rest[i + 1..];
+        let run = rest.bytes().take_while(|b| *b == b'#').count();
+        hashes = hashes.max(run + 1);
+    }
+    hashes
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::{format, vec};
+
+    use super::*;
+    use crate::{unescape_regular_string, LexerOptions, Number, Parser, ParserOptions};
+
+    const DOC: &str = r##"/// A blog post.
+{
+  /// The title of the post.
+  title "Hello world"
+  tags [
+    "rust",
+    #"say "hi"#
+  ]
+  meta {
+    /// Whether the post is a draft.
+    draft false
+  }
+  empty {}
+}
+"##;
+
+    #[test]
+    fn default_options() {
+        let doc = Parser::from(DOC).parse().unwrap();
+        assert_eq!(to_string(&doc, &WriterOptions::default()).unwrap(), DOC);
+    }
+
+    #[test]
+    fn indentation() {
+        let doc = Parser::from(DOC).parse().unwrap();
+        let expected = r##"/// A blog post.
+{
+    /// The title of the post.
+    title "Hello world"
+    tags [
+        "rust",
+        #"say "hi"#
+    ]
+    meta {
+        /// Whether the post is a draft.
+        draft false
+    }
+    empty {}
+}
+"##;
+        let opts = WriterOptions {
+            indent: 4,
+            ..Default::default()
+        };
+        let actual = to_string(&doc, &opts).unwrap();
+        assert_eq!(actual, expected);
+        // The output must parse back to the same document
+        assert_eq!(Parser::from(actual.as_str()).parse().unwrap(), doc);
+    }
+
+    #[test]
+    fn suppressed_docstrings_and_sorted_keys() {
+        let doc = Parser::from(DOC).parse().unwrap();
+        let expected = r##"{
+  empty {}
+  meta {
+    draft false
+  }
+  tags [
+    "rust",
+    #"say "hi"#
+  ]
+  title "Hello world"
+}
+"##;
+        let opts = WriterOptions {
+            docstrings: false,
+            sort_keys: true,
+            ..Default::default()
+        };
+        assert_eq!(to_string(&doc, &opts).unwrap(), expected);
+    }
+
+    #[test]
+    fn scalar_values() {
+        let doc = Document {
+            docstring: vec![],
+            value: Value::Array(vec![
+                Value::Number(Number::Unsigned(10)),
+                Value::Number(Number::Signed(-10)),
+                Value::Number(Number::Float(4.0)),
+                Value::Date("2022-01-02".parse().unwrap()),
+                Value::DateTime("2022-01-02T03:04:05.5+02:00".parse().unwrap()),
+                Value::String(ValueString::Dedent(MaybeLiteralString::NonLiteral(vec![
+                    "first", "second",
+                ]))),
+            ]),
+        };
+        let expected = r#"[
+  10,
+  -10,
+  4.0,
+  2022-01-02,
+  2022-01-02T03:04:05.5+02:00,
+  d"first
+second"
+]
+"#;
+        assert_eq!(
+            to_string(&doc, &WriterOptions::default()).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn non_finite_floats() {
+        for f in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let doc = Document {
+                docstring: vec![],
+                value: Value::Object(vec![DocumentedProperty {
+                    docstring: vec![],
+                    id: "nested",
+                    value: Value::Array(vec![Value::Number(Number::Float(f))]),
+                }]),
+            };
+            assert_eq!(
+                to_string(&doc, &WriterOptions::default()),
+                Err(Error::NonFiniteNumber),
+                "test case: {}",
+                f
+            );
+        }
+    }
+
+    #[test]
+    fn write_parse_round_trip() {
+        const SRC: &str = r##"/// Numbers and nesting.
+{
+  nullable null
+  trueish true
+  floats [1e100, -2.5e-8, 0.1, -0.0]
+  ints [0, 18446744073709551615, -9223372036854775808]
+  /// An array of objects.
+  objects [{ a null }, {}, { b [[], [true, false]] }]
+  when [2000-01-01, 2000-01-01T00:00:00.123-05:00]
+  strings ["a\tb", #"c "quoted" d"#, d"
+    dedented
+  "]
+}
+"##;
+        let doc = Parser::from(SRC).parse().unwrap();
+        for opts in [
+            WriterOptions::default(),
+            WriterOptions {
+                indent: 0,
+                sort_keys: true,
+                ..Default::default()
+            },
+        ] {
+            let written = to_string(&doc, &opts).unwrap();
+            let reparsed = Parser::from(written.as_str()).parse().unwrap();
+            let mut expected = doc.clone();
+            if opts.sort_keys {
+                expected.value.sort_object_keys();
+            }
+            assert_eq!(reparsed, expected, "written:\n{}", written);
+        }
+    }
+
+    #[test]
+    fn single_quoted_strings_round_trip() {
+        const SRC: &str = r#"{ a 'say "hi"' b 'it\'s \"quoted\"' c 'back\\' }"#;
+        let opts = ParserOptions {
+            lexer: LexerOptions {
+                single_quoted_strings: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let doc = Parser::new(SRC, opts).parse().unwrap();
+        let written = to_string(&doc, &WriterOptions::default()).unwrap();
+        let reparsed = Parser::from(written.as_str()).parse().unwrap();
+        for (id, expected) in [
+            ("a", "say \"hi\""),
+            ("b", "it's \"quoted\""),
+            ("c", "back\\"),
+        ] {
+            assert_eq!(
+                reparsed.value.get(id).unwrap().as_str().unwrap(),
+                expected,
+                "written:\n{}",
+                written
+            );
+        }
+    }
+
+    #[test]
+    fn overlong_literal_delimiters() {
+        let text = alloc::format!("a \"{} b", "#".repeat(MAX_LITERAL_HASHES));
+        let quoted = quote_string(&text);
+        assert!(quoted.starts_with("\"a \\\""), "quoted: {}", quoted);
+        assert_eq!(
+            Parser::from(quoted.as_str())
+                .parse()
+                .unwrap()
+                .value
+                .as_str()
+                .unwrap(),
+            text
+        );
+
+        let lines = vec![text.as_str(), "second"];
+        let doc = Document {
+            docstring: vec![],
+            value: Value::String(ValueString::Regular(MaybeLiteralString::Literal(
+                lines.clone(),
+            ))),
+        };
+        let written = to_string(&doc, &WriterOptions::default()).unwrap();
+        let reparsed = Parser::from(written.as_str()).parse().unwrap();
+        assert_eq!(
+            reparsed.value.as_str().unwrap(),
+            doc.value.as_str().unwrap()
+        );
+
+        let doc = Document {
+            docstring: vec![],
+            value: Value::String(ValueString::Dedent(MaybeLiteralString::Literal(lines))),
+        };
+        assert_eq!(
+            to_string(&doc, &WriterOptions::default()),
+            Err(Error::StringLiteralDelimTooLong {
+                len: MAX_LITERAL_HASHES + 1,
+                max_len: MAX_LITERAL_HASHES,
+            })
+        );
+    }
+
+    #[test]
+    fn literal_string_delimiters() {
+        const TEST_CASES: &[(&str, usize)] = &[
+            ("plain", 1),
+            ("\"quoted\"", 1),
+            ("\"#", 2),
+            ("a \"## b \"# c", 3),
+        ];
+        for (tc, expected) in TEST_CASES {
+            assert_eq!(choose_literal_hashes(tc), *expected, "test case: {}", tc);
+        }
+    }
+
+    #[test]
+    fn regular_string_escaping() {
+        const TEST_CASES: &[(&str, &str)] = &[
+            ("plain text", "plain text"),
+            ("", ""),
+            ("two\nlines", "two\\nlines"),
+            ("tab\tand\r\n", "tab\\tand\\r\\n"),
+            ("say \"hi\"#", "say \\\"hi\\\"#"),
+            ("back\\slash", "back\\\\slash"),
+            (
+                "bell\x07 esc\x1b del\x7f",
+                "bell\\u{07} esc\\u{1b} del\\u{7f}",
+            ),
+            ("ünicode 😂", "ünicode 😂"),
+        ];
+        for (tc, expected) in TEST_CASES {
+            assert_eq!(escape_regular_string(tc), *expected, "test case: {:?}", tc);
+        }
+    }
+
+    #[test]
+    fn string_quoting() {
+        const TEST_CASES: &[(&str, &str)] = &[
+            ("plain", "\"plain\""),
+            ("two\nlines", "\"two\\nlines\""),
+            ("say \"hi\"#", "##\"say \"hi\"#\"##"),
+            ("C:\\dir\\", "#\"C:\\dir\\\"#"),
+            ("a\\\r\n", "\"a\\\\\\r\\n\""),
+        ];
+        for (tc, expected) in TEST_CASES {
+            assert_eq!(quote_string(tc), *expected, "test case: {:?}", tc);
+        }
+    }
+
+    // Parses the given quoted string and decodes its content
+    fn parse_quoted(src: &str) -> String {
+        let doc = Parser::from(src).parse().unwrap();
+        match doc.value {
+            Value::String(ValueString::Regular(MaybeLiteralString::NonLiteral(lines))) => {
+                unescape_regular_string(&lines.join("\n"))
+                    .unwrap()
+                    .into_owned()
+            }
+            Value::String(ValueString::Regular(MaybeLiteralString::Literal(lines))) => {
+                lines.join("\n")
+            }
+            v => panic!("unexpected value: {:?}", v),
+        }
+    }
+
+    #[test]
+    fn strings_round_trip() {
+        const TEST_CASES: &[&str] = &[
+            "plain",
+            "",
+            "two\nlines",
+            "tab\tand\r\n",
+            "say \"hi\"#",
+            "a\\",
+            "C:\\dir\\",
+            "\\\"",
+            "bell\x07 and \\",
+            "ünicode 😂 \"quoted\"",
+        ];
+        for tc in TEST_CASES {
+            let escaped = format!("\"{}\"", escape_regular_string(tc));
+            assert_eq!(parse_quoted(&escaped), *tc, "escaped: {}", escaped);
+            let quoted = quote_string(tc);
+            assert_eq!(parse_quoted(&quoted), *tc, "quoted: {}", quoted);
+        }
+    }
+}